@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use specs::{prelude::*, Component};
 use specs_derive::Component;
 
-use crate::components::Velocity;
+use crate::components::{Burden, Position, Velocity};
 
 #[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Action {
@@ -17,11 +17,21 @@ pub enum Action {
     },
     PickUp,
     Drop {
-        inventory_index: usize,
+        inventory_indices: Vec<usize>,
     },
     UseFromInventory {
         inventory_index: usize,
     },
+    Fire {
+        target: Position,
+    },
+    Throw {
+        inventory_index: usize,
+        target: Position,
+    },
+    CloseDoor,
+    Search,
+    Disarm,
 }
 
 impl Action {
@@ -34,6 +44,21 @@ impl Action {
             WaitForInput => 0,
             Drop { .. } => 1,
             UseFromInventory { .. } => 1,
+            Fire { .. } => 1,
+            Throw { .. } => 1,
+            CloseDoor => 1,
+            Search => 1,
+            Disarm => 1,
+        }
+    }
+
+    /// Like `energy_cost`, but moving and attacking take longer when carrying too much
+    pub fn energy_cost_with(self: &Self, burden: Option<&Burden>) -> u8 {
+        match self {
+            Action::MoveOrMelee { .. } => {
+                self.energy_cost() * burden.map_or(1, |b| b.energy_multiplier())
+            }
+            _ => self.energy_cost(),
         }
     }
 
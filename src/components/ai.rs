@@ -9,5 +9,9 @@ pub enum Ai {
         previous_ai: Box<Ai>,
         num_turns: i32,
     },
-    Player
+    Alerted {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+    Player,
 }
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+
+/// How weighed down an entity is by the contents of its inventory.
+/// Calculated by `EncumbranceSystem`, so there's no need to save it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub enum Burden {
+    Unburdened,
+    Burdened,
+    Strained,
+}
+
+impl Burden {
+    pub fn from_weight(carried: i32, limit: i32) -> Burden {
+        if carried * 2 > limit * 3 {
+            Burden::Strained
+        } else if carried > limit {
+            Burden::Burdened
+        } else {
+            Burden::Unburdened
+        }
+    }
+
+    /// Moving and fighting gets slower as the load gets heavier
+    pub fn energy_multiplier(self) -> u8 {
+        match self {
+            Burden::Unburdened => 1,
+            Burden::Burdened => 2,
+            Burden::Strained => 3,
+        }
+    }
+
+    pub fn describe(self) -> Option<&'static str> {
+        match self {
+            Burden::Unburdened => None,
+            Burden::Burdened => Some("Burdened"),
+            Burden::Strained => Some("Strained"),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+
+/// The player stumbles in a random direction when they try to move, for this many more turns.
+/// Monsters get confused through their `Ai` instead.
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub struct Confused(pub i32);
@@ -6,20 +6,121 @@ use specs::{
 };
 use specs_derive::Component;
 
+pub const DEFAULT_INVENTORY_CAPACITY: usize = 30;
+pub const DEFAULT_WEIGHT_LIMIT: i32 = 20;
+
+/// A pile of identical items occupying a single inventory slot.
+/// Each item keeps its own entity, so splitting a stack is just handing one of them back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stack(pub Vec<Entity>);
+
+impl Stack {
+    pub fn top(&self) -> Entity {
+        *self.0.last().unwrap()
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
 #[derive(Component, Clone)]
 #[storage(HashMapStorage)]
-pub struct Inventory(pub Vec<Entity>);
+pub struct Inventory {
+    pub stacks: Vec<Stack>,
+    /// Maximum number of stacks (slots), not items
+    pub capacity: usize,
+    /// Carrying more than this slows the owner down, see `Burden`
+    pub weight_limit: i32,
+}
 
 impl Inventory {
     pub fn new() -> Inventory {
-        Inventory(Vec::new())
+        Inventory::with_capacity(DEFAULT_INVENTORY_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Inventory {
+        Inventory {
+            stacks: Vec::new(),
+            capacity,
+            weight_limit: DEFAULT_WEIGHT_LIMIT,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &Entity> {
+        self.stacks.iter().flat_map(|stack| stack.0.iter())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.stacks.len() >= self.capacity
+    }
+
+    /// The item that'd be used or dropped from the given slot
+    pub fn get(&self, index: usize) -> Option<Entity> {
+        self.stacks.get(index).map(Stack::top)
+    }
+
+    /// Index of the first stack whose items satisfy `is_same`
+    pub fn find_stack<F>(&self, is_same: F) -> Option<usize>
+    where
+        F: Fn(Entity) -> bool,
+    {
+        self.stacks.iter().position(|stack| is_same(stack.top()))
+    }
+
+    pub fn index_of(&self, item: Entity) -> Option<usize> {
+        self.stacks.iter().position(|stack| stack.0.contains(&item))
+    }
+
+    /// Add an item on top of an existing stack, or into a new slot if `stack` is `None`.
+    /// Returns false if a new slot was needed, but the inventory is full.
+    pub fn push(&mut self, item: Entity, stack: Option<usize>) -> bool {
+        match stack.and_then(|index| self.stacks.get_mut(index)) {
+            Some(stack) => stack.0.push(item),
+            None => {
+                if self.is_full() {
+                    return false;
+                }
+                self.stacks.push(Stack(vec![item]));
+            }
+        }
+        true
+    }
+
+    /// Split a single item off the given slot, freeing up the slot if it was the last one
+    pub fn take(&mut self, index: usize) -> Option<Entity> {
+        let item = self.stacks.get_mut(index)?.0.pop();
+        if self.stacks[index].0.is_empty() {
+            self.stacks.remove(index);
+        }
+        item
+    }
+
+    /// Forget about an item wherever it is
+    pub fn remove(&mut self, item: Entity) {
+        for stack in &mut self.stacks {
+            stack.0.retain(|&x| x != item);
+        }
+        self.stacks.retain(|stack| !stack.0.is_empty());
     }
 }
 
 // I wish specs_derive could do this :(
 
 #[derive(Serialize, Deserialize)]
-pub struct InventoryData<M>(pub Vec<M>);
+pub struct InventoryData<M> {
+    pub stacks: Vec<Vec<M>>,
+    pub capacity: usize,
+    pub weight_limit: i32,
+}
 
 impl<M: Marker + Serialize> ConvertSaveload<M> for Inventory
 where
@@ -32,17 +133,29 @@ where
     where
         F: FnMut(M) -> Option<Entity>,
     {
-        Ok(Inventory(
-            data.0.iter().map(|id| ids(id.clone()).unwrap()).collect(),
-        ))
+        Ok(Inventory {
+            stacks: data
+                .stacks
+                .iter()
+                .map(|stack| Stack(stack.iter().map(|id| ids(id.clone()).unwrap()).collect()))
+                .collect(),
+            capacity: data.capacity,
+            weight_limit: data.weight_limit,
+        })
     }
 
     fn convert_into<F>(&self, mut ids: F) -> Result<Self::Data, Self::Error>
     where
         F: FnMut(Entity) -> Option<M>,
     {
-        Ok(InventoryData(
-            self.0.iter().map(|&e| ids(e).unwrap()).collect(),
-        ))
+        Ok(InventoryData {
+            stacks: self
+                .stacks
+                .iter()
+                .map(|stack| stack.0.iter().map(|&e| ids(e).unwrap()).collect())
+                .collect(),
+            capacity: self.capacity,
+            weight_limit: self.weight_limit,
+        })
     }
 }
@@ -2,10 +2,13 @@ use serde::{Deserialize, Serialize};
 use specs::{Component, DenseVecStorage};
 use specs_derive::Component;
 
-#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Item {
     Heal,
     Lightning,
     Confuse,
     Fireball,
+    Weapon,
+    Ammo,
+    Key,
 }
@@ -9,4 +9,24 @@ pub struct Living {
     pub max_hp: i32,
     pub hp: i32,
     pub defense: i32,
+    /// Whatever hurt this last, so we know what killed it
+    #[serde(default)]
+    pub last_damage: Option<Damage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Damage {
+    /// Fits after "killed by", like "the orc" or "lava"
+    pub cause: String,
+    pub by_player: bool,
+}
+
+impl Living {
+    pub fn take_damage<T: Into<String>>(&mut self, amount: i32, cause: T, by_player: bool) {
+        self.hp -= amount;
+        self.last_damage = Some(Damage {
+            cause: cause.into(),
+            by_player,
+        });
+    }
 }
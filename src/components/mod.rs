@@ -1,6 +1,8 @@
 mod action;
 mod ai;
+mod burden;
 mod collider;
+mod confused;
 mod energy;
 mod inventory;
 mod item;
@@ -9,19 +11,29 @@ mod name;
 mod player;
 mod position;
 mod power;
+mod ranged;
+mod thrown_damage;
+mod trap;
 pub mod velocity;
 mod visual;
+mod weight;
 
 pub use action::Action;
 pub use ai::Ai;
+pub use burden::Burden;
 pub use collider::Collider;
+pub use confused::Confused;
 pub use energy::Energy;
 pub use inventory::Inventory;
 pub use item::Item;
-pub use living::Living;
+pub use living::{Damage, Living};
 pub use name::Name;
 pub use player::Player;
 pub use position::{Position, PreviousPosition};
 pub use power::Power;
+pub use ranged::{Ammo, AmmoKind, Launcher};
+pub use thrown_damage::ThrownDamage;
+pub use trap::{Trap, TrapKind};
 pub use velocity::Velocity;
 pub use visual::Visual;
+pub use weight::Weight;
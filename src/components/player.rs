@@ -4,10 +4,17 @@ use specs_derive::Component;
 
 #[derive(PartialEq, Component, Debug, Clone, Serialize, Deserialize)]
 #[storage(HashMapStorage)]
-pub struct Player;
+pub struct Player {
+    /// How many turns the game has been going
+    #[serde(default)]
+    pub turns: u32,
+    /// Monsters slain by the player
+    #[serde(default)]
+    pub kills: u32,
+}
 
 impl Player {
     pub fn new() -> Player {
-        Player
+        Player { turns: 0, kills: 0 }
     }
 }
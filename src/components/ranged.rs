@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AmmoKind {
+    Arrow,
+    Stone,
+}
+
+/// A weapon that shoots `Ammo` of the matching kind from the owner's inventory
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub struct Launcher {
+    pub ammo: AmmoKind,
+    pub damage: i32,
+    pub range: i32,
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub struct Ammo {
+    pub kind: AmmoKind,
+    pub damage: i32,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+
+/// Damage dealt when the item is thrown at something, before defense
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub struct ThrownDamage(pub i32);
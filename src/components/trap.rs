@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrapKind {
+    Spikes,
+    Teleport,
+    Alarm,
+    ConfusionGas,
+    Trapdoor,
+}
+
+/// Goes off when something steps on it. Not drawn until `revealed`.
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[storage(HashMapStorage)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub revealed: bool,
+}
+
+impl Trap {
+    pub fn hidden(kind: TrapKind) -> Trap {
+        Trap {
+            kind,
+            revealed: false,
+        }
+    }
+}
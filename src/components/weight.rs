@@ -0,0 +1,6 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, DenseVecStorage};
+use specs_derive::Component;
+
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Weight(pub i32);
@@ -0,0 +1,121 @@
+//! Dump the current level to a text file and a PNG, to find out what went wrong in mapgen
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use specs::{Join, World};
+use tcod::image::Image;
+
+use crate::{
+    components::{Collider, Position, Visual},
+    resources::map::{Map, TileKind},
+    systems::render::tile_appearance,
+};
+
+/// Each tile is a square of this many pixels in the PNG
+const PNG_SCALE: i32 = 4;
+
+/// Uses the prefab legend where it can, so a dump can be cut up into new prefabs
+fn tile_char(kind: &TileKind) -> char {
+    match kind {
+        TileKind::Floor => '.',
+        TileKind::Wall => '#',
+        TileKind::Door(door) if door.open => '\'',
+        TileKind::Door(door) if door.locked => '=',
+        TileKind::Door(_) => '+',
+        TileKind::ShallowWater => '~',
+        TileKind::DeepWater => 'w',
+        TileKind::Lava => '%',
+        TileKind::Chasm => '_',
+        TileKind::Rubble => ':',
+    }
+}
+
+/// Everything with a position and a look, things with a collider (like the player) last,
+/// so they end up on top
+pub fn objects(world: &World) -> Vec<(Position, Visual)> {
+    let position = world.read_storage::<Position>();
+    let visual = world.read_storage::<Visual>();
+    let collider = world.read_storage::<Collider>();
+    let mut objects: Vec<_> = (&position, &visual, (&collider).maybe())
+        .join()
+        .map(|(position, visual, collider)| (collider.is_some(), position.clone(), visual.clone()))
+        .collect();
+    objects.sort_by_key(|object| object.0);
+    objects
+        .into_iter()
+        .map(|(_, position, visual)| (position, visual))
+        .collect()
+}
+
+/// The whole level, explored or not, one line per row
+pub fn to_ascii(map: &Map, objects: &[(Position, Visual)]) -> String {
+    let mut rows: Vec<Vec<char>> = (0..map.height)
+        .map(|y| {
+            (0..map.width)
+                .map(|x| tile_char(&map.tiles[x as usize][y as usize].kind))
+                .collect()
+        })
+        .collect();
+    for (position, visual) in objects {
+        if map.in_bounds(position) {
+            rows[position.y as usize][position.x as usize] = visual.char;
+        }
+    }
+    rows.into_iter()
+        .map(|row| row.into_iter().collect::<String>() + "\n")
+        .collect()
+}
+
+/// Explored tiles are drawn lit, unexplored ones as if they were out of sight.
+/// Objects are a smaller square in their own colour.
+pub fn to_image(map: &Map, objects: &[(Position, Visual)]) -> Image {
+    let mut image = Image::new(map.width * PNG_SCALE, map.height * PNG_SCALE);
+    let mut fill = |x: i32, y: i32, inset: i32, color| {
+        for px in (x * PNG_SCALE + inset)..((x + 1) * PNG_SCALE - inset) {
+            for py in (y * PNG_SCALE + inset)..((y + 1) * PNG_SCALE - inset) {
+                image.put_pixel(px, py, color);
+            }
+        }
+    };
+    for x in 0..map.width {
+        for y in 0..map.height {
+            let tile = &map.tiles[x as usize][y as usize];
+            let (background, _) = tile_appearance(&tile.kind, tile.explored);
+            fill(x, y, 0, background);
+        }
+    }
+    for (position, visual) in objects {
+        if map.in_bounds(position) {
+            fill(position.x, position.y, 1, visual.color);
+        }
+    }
+    image
+}
+
+/// Write `<base>.txt` and `<base>.png`, returning their paths
+pub fn export(world: &World, base: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    let map = world.read_resource::<Map>();
+    let objects = objects(world);
+    let text_path = base.with_extension("txt");
+    let png_path = base.with_extension("png");
+
+    fs::write(&text_path, to_ascii(&map, &objects))?;
+
+    // tcod doesn't say whether saving worked, so get rid of the last export's image first,
+    // and check that there's a new one afterwards
+    if let Err(e) = fs::remove_file(&png_path) {
+        if e.kind() != io::ErrorKind::NotFound {
+            return Err(e);
+        }
+    }
+    to_image(&map, &objects).save(&png_path);
+    if !png_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("couldn't write {}", png_path.display()),
+        ));
+    }
+    Ok((text_path, png_path))
+}
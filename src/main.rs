@@ -1,11 +1,17 @@
 #![feature(slice_patterns)]
 
 mod components;
+mod export;
 mod mapgen;
 mod meta_dispatcher;
+mod morgue;
 mod resources;
+mod rexpaint;
+mod save_format;
 mod systems;
 
+use std::path::Path;
+
 use specs::{
     prelude::*,
     saveload::{MarkedBuilder, U64Marker, U64MarkerAllocator},
@@ -19,17 +25,26 @@ use crate::{
     components::*,
     meta_dispatcher::MetaDispatcher,
     resources::{
+        identification::Identification,
         input_action::InputAction,
+        keymap::{Keymap, KeymapError},
         map::Map,
-        menu::{Menu, MenuKind},
+        menu::{Menu, MenuItem, MenuResult},
         messages::Messages,
+        save_error::SaveError,
+        save_slot::{ExplorerMode, SaveSlot},
         state::State,
         targeting::Targeting,
         ui::{self, UIConfig, UIState, PANEL_HEIGHT},
     },
+    save_format::SaveBackend,
     systems::{save::Synthetic, *},
 };
 
+/// Where the F12 and `--export-map` dumps go, without the extension
+const EXPORT_PATH: &str = "map_dump";
+const DEATH_SCREEN_WIDTH: i32 = 50;
+
 fn build_dispatcher<'a, 'b>() -> MetaDispatcher<'a, 'b> {
     let mut meta = MetaDispatcher::new();
 
@@ -38,6 +53,8 @@ fn build_dispatcher<'a, 'b>() -> MetaDispatcher<'a, 'b> {
             .with(LocationHistorySystem, "location_history", &[])
             .with(InputSystem, "input", &[])
             .with(MenuSystem, "menu", &["input"])
+            .with(KeymapSystem, "keymap", &["menu"])
+            .with(HelpSystem, "help", &["menu"])
             .build(),
     );
 
@@ -84,13 +101,43 @@ fn build_dispatcher<'a, 'b>() -> MetaDispatcher<'a, 'b> {
             .with(AISystem, "ai", &[])
             .with(MoveAndMeleeSystem, "move_and_melee", &["ai"])
             .with(CollisionSystem, "collision", &["move_and_melee"])
-            .with(MovementSystem, "movement", &["move_and_melee", "collision"])
+            .with(TerrainSystem, "terrain", &["collision"])
+            .with(TrapSystem, "trap", &["collision"])
+            .with(
+                MovementSystem,
+                "movement",
+                &["move_and_melee", "collision", "terrain", "trap"],
+            )
             .with(SkipSystem, "skip", &["ai"])
+            .with(DoorSystem, "door", &["ai"])
+            .with(SearchSystem, "search", &["ai"])
             .with(DropSystem, "drop", &["ai"])
             .with(UseItemSystem, "use_item", &["ai"])
+            .with(FireSystem, "fire", &["ai"])
+            .with(ThrowSystem, "throw", &["ai"])
             .with(PickUpSystem, "pick_up", &["ai"])
-            .with(MonsterDeathSystem, "monster_death", &["move_and_melee"])
-            .with(PlayerDeathSystem, "player_death", &["move_and_melee"])
+            .with(
+                EncumbranceSystem,
+                "encumbrance",
+                &["pick_up", "drop", "use_item", "fire", "throw"],
+            )
+            .with(
+                MonsterDeathSystem,
+                "monster_death",
+                &["move_and_melee", "fire", "throw", "terrain", "trap"],
+            )
+            .with(
+                PlayerDeathSystem,
+                "player_death",
+                &[
+                    "move_and_melee",
+                    "fire",
+                    "throw",
+                    "terrain",
+                    "trap",
+                    "monster_death",
+                ],
+            )
             .build(),
     );
 
@@ -109,7 +156,12 @@ fn build_dispatcher<'a, 'b>() -> MetaDispatcher<'a, 'b> {
 fn setup_ecs(world: &mut World, dispatcher: &mut MetaDispatcher) {
     world.add_resource::<Option<Targeting>>(None);
     world.add_resource::<Option<Menu>>(None);
+    world.add_resource::<Option<SaveError>>(None);
+    world.add_resource(SaveBackend::default());
+    world.add_resource(ExplorerMode::default());
     world.add_resource(U64MarkerAllocator::new());
+    world.add_resource(Identification::default());
+    world.register::<Identification>();
     world.register::<Item>();
     world.register::<U64Marker>();
     world.register::<Map>();
@@ -119,10 +171,21 @@ fn setup_ecs(world: &mut World, dispatcher: &mut MetaDispatcher) {
 }
 
 fn welcome_message(world: &mut World) {
-    world.write_resource::<Messages>().push(
+    let mut messages = world.write_resource::<Messages>();
+    messages.push(
         "Welcome stranger! Prepare to perish in the Tombs of the Ancient Kings.",
         colors::RED,
     );
+    if let Some(key) = world
+        .read_resource::<Keymap>()
+        .keys_for(&InputAction::ShowHelp)
+        .first()
+    {
+        messages.push(
+            format!("Press {} to see what the keys do.", key),
+            colors::LIGHT_GREY,
+        );
+    }
 }
 
 fn initialize_ui(world: &mut World) {
@@ -132,16 +195,16 @@ fn initialize_ui(world: &mut World) {
     world.add_resource(Mouse::default());
 }
 
-fn new_map(world: &mut World) {
-    Map::new_random(world);
+fn new_map(world: &mut World, depth: u32) {
+    Map::new_random(world, depth);
 }
 
 fn create_fov_map(world: &mut World) {
-    let fov_map = systems::fov::new_fov_map(&world.read_resource::<Map>().tiles);
+    let fov_map = systems::fov::new_fov_map(&world.read_resource::<Map>());
     world.add_resource(fov_map);
 }
 
-fn spawn_player(world: &mut World) {
+fn spawn_player(world: &mut World, name: &str) {
     let spawn_point = world.read_resource::<Map>().spawn_point.clone();
     world
         .create_entity()
@@ -154,13 +217,14 @@ fn spawn_player(world: &mut World) {
         })
         .with(Collider::new())
         .with(Player::new())
-        .with(Name::new("player"))
+        .with(Name::new(name))
         .with(PreviousPosition { x: -1, y: -1 })
         .with(Living {
             alive: true,
             max_hp: 30,
             hp: 30,
             defense: 2,
+            last_damage: None,
         })
         .with(Power(5))
         .with(Inventory::new())
@@ -184,8 +248,9 @@ fn next_level(world: &mut World) {
         .collect();
     world.delete_entities(&entities).unwrap();
 
-    // Then create the new map
-    new_map(world);
+    // Then create the new map, one level deeper
+    let depth = world.read_resource::<Map>().depth + 1;
+    new_map(world, depth);
     create_fov_map(world);
 
     // And move the player to the spawn point of the new map
@@ -199,10 +264,12 @@ fn next_level(world: &mut World) {
         .write_storage::<Position>()
         .insert(player_entity, spawn_point)
         .unwrap();
+
+    autosave(world);
 }
 
 fn get_action(world: &World) -> InputAction {
-    *world.read_resource::<InputAction>()
+    world.read_resource::<InputAction>().clone()
 }
 
 fn exited(world: &World) -> bool {
@@ -221,29 +288,83 @@ fn game_loop(world: &mut World, dispatcher: &mut MetaDispatcher) {
     while !exited(world) && !window_closed(world) {
         world.maintain();
         {
-            match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
-                Some((_, Event::Mouse(m))) => {
-                    *world.write_resource() = m;
-                    *world.write_resource::<Option<Key>>() = None;
-                }
-                Some((_, Event::Key(k))) => *world.write_resource() = Some(k),
-                _ => *world.write_resource::<Option<Key>>() = None,
+            let event = input::check_for_event(input::MOUSE | input::KEY_PRESS);
+            if let Some((_, Event::Mouse(m))) = event {
+                *world.write_resource() = m;
+            } else {
+                // A click only counts in the frame it happened in
+                let mut mouse = world.write_resource::<Mouse>();
+                mouse.lbutton_pressed = false;
+                mouse.rbutton_pressed = false;
             }
+            *world.write_resource::<Option<Key>>() = match event {
+                Some((_, Event::Key(k))) => Some(k),
+                _ => None,
+            };
         }
         dispatcher.dispatch(&world);
+        if *world.read_resource::<State>() == State::Descending {
+            next_level(world);
+            world.add_resource(State::Game);
+        }
         match get_action(world) {
-            InputAction::NewGame => new_game(world),
-            InputAction::LoadGame => load_game(world),
+            InputAction::NewGame(name, slot) => new_game(world, &name, slot),
+            InputAction::LoadGame(slot) => load_game(world, slot),
             InputAction::MainMenu => {
-                save_game(world);
+                end_game(world);
+                main_menu(world);
+                dispatcher.dispatch(&world);
+            }
+            InputAction::SaveGame(slot) => {
+                save_game(world, slot);
                 end_game(world);
                 main_menu(world);
                 dispatcher.dispatch(&world);
             }
             InputAction::NextLevel => next_level(world),
+            InputAction::ExportMap => export_map(world),
             _ => (),
         }
+        if *world.read_resource::<State>() == State::GameOver
+            && world.read_resource::<Option<Menu>>().is_none()
+        {
+            game_over(world);
+        }
+    }
+
+    // closing the window shouldn't cost the player their run
+    if window_closed(world) && *world.read_resource::<State>() == State::Game && player_alive(world)
+    {
+        autosave(world);
+    }
+}
+
+/// Show the death screen, and leave a morgue file behind
+fn game_over(world: &mut World) {
+    let mut header = morgue::summary(world);
+    match morgue::write(world) {
+        Ok(path) => header += &format!("\nMorgue file written to {}\n", path.display()),
+        Err(e) => header += &format!("\nCould not write the morgue file: {}\n", e),
     }
+    let menu = Menu::new(
+        header,
+        DEATH_SCREEN_WIDTH,
+        vec![MenuItem::new("Back to the main menu")],
+        |_| MenuResult::Action(InputAction::MainMenu),
+    )
+    .on_dismiss(|_| MenuResult::Action(InputAction::MainMenu));
+    world.add_resource(Some(menu));
+}
+
+fn export_map(world: &mut World) {
+    let (message, color) = match export::export(world, Path::new(EXPORT_PATH)) {
+        Ok((text, png)) => (
+            format!("Map exported to {} and {}", text.display(), png.display()),
+            colors::LIGHT_GREY,
+        ),
+        Err(e) => (format!("Failed to export map: {}", e), colors::RED),
+    };
+    world.write_resource::<Messages>().push(message, color);
 }
 
 fn end_game(world: &mut World) {
@@ -252,24 +373,64 @@ fn end_game(world: &mut World) {
     world.maintain();
 }
 
-fn new_game(world: &mut World) {
+fn new_game(world: &mut World, name: &str, slot: usize) {
     end_game(world);
-    new_map(world);
+    world.add_resource(Identification::new_random());
+    new_map(world, 1);
     create_fov_map(world);
-    spawn_player(world);
+    spawn_player(world, name);
     welcome_message(world);
+
+    // the player already agreed to lose whatever game was in the slot
+    match SaveSlot(slot).delete() {
+        Ok(()) | Err(SaveError::NoSave) => (),
+        Err(e) => world
+            .write_resource::<Messages>()
+            .push(e.to_string(), colors::RED),
+    }
+    world.add_resource(SaveSlot(slot));
     world.add_resource(State::Game);
     world.maintain();
 }
 
-fn save_game(world: &mut World) {
+fn save_game(world: &mut World, slot: usize) {
+    let previous = *world.read_resource::<SaveSlot>();
+    world.add_resource(SaveSlot(slot));
     SavePrepSystem.run_now(&world.res);
     world.maintain();
     SaveSystem.run_now(&world.res);
     world.maintain();
+
+    // the game moved to another slot, so there's no copy left behind to reload after dying
+    if previous.0 != slot && world.read_resource::<Option<SaveError>>().is_none() {
+        match previous.delete() {
+            Ok(()) | Err(SaveError::NoSave) => (),
+            Err(e) => *world.write_resource::<Option<SaveError>>() = Some(e),
+        }
+    }
 }
 
-fn load_game(world: &mut World) {
+/// Save the current game into its own slot, and keep playing
+fn autosave(world: &mut World) {
+    let slot = world.read_resource::<SaveSlot>().0;
+    save_game(world, slot);
+    if let Some(error) = world.write_resource::<Option<SaveError>>().take() {
+        world
+            .write_resource::<Messages>()
+            .push(format!("Autosave failed. {}", error), colors::RED);
+    }
+}
+
+fn player_alive(world: &World) -> bool {
+    (
+        &world.read_storage::<Living>(),
+        &world.read_storage::<Player>(),
+    )
+        .join()
+        .any(|(living, _)| living.alive)
+}
+
+fn load_game(world: &mut World, slot: usize) {
     // Start from a clean state
     end_game(world);
 
@@ -278,8 +439,16 @@ fn load_game(world: &mut World) {
     world.add_resource(Map::empty());
     world.add_resource(Messages::new(0));
 
-    // Do the actual loading
+    // Do the actual loading. If it fails, whatever got loaded is thrown away again, and the
+    // main menu tells the player what went wrong.
+    world.add_resource(SaveSlot(slot));
     LoadSystem.run_now(&world.res);
+    world.maintain();
+    if world.read_resource::<Option<SaveError>>().is_some() {
+        end_game(world);
+        main_menu(world);
+        return;
+    }
     create_fov_map(world);
 
     // Start the game
@@ -293,12 +462,56 @@ fn main_menu(world: &mut World) {
     world.add_resource(InputAction::Noop);
 }
 
+/// Generate a level without opening a window, and dump it. For debugging map generation.
+fn export_headless(depth: u32) {
+    let mut world = World::new();
+    let mut dispatcher = build_dispatcher();
+    setup_ecs(&mut world, &mut dispatcher);
+    world.add_resource(Identification::new_random());
+    new_map(&mut world, depth);
+    spawn_player(&mut world, "player");
+
+    match export::export(&world, Path::new(EXPORT_PATH)) {
+        Ok((text, png)) => println!("Map exported to {} and {}", text.display(), png.display()),
+        Err(e) => {
+            eprintln!("Failed to export map: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
+    // `--export-map [depth]` dumps a freshly generated level instead of starting the game
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--export-map") {
+        let depth = args
+            .get(index + 1)
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(1);
+        export_headless(depth);
+        return;
+    }
+
     let mut world = World::new();
     let mut dispatcher = build_dispatcher();
     setup_ecs(&mut world, &mut dispatcher);
     initialize_ui(&mut world);
 
+    // bad key bindings are reported on the main menu, and the default ones used instead
+    match Keymap::load() {
+        Ok(keymap) => world.add_resource(keymap),
+        Err(e) => world.add_resource(Some(KeymapError(e))),
+    }
+
+    // `--ron-saves` writes saves as readable text, for debugging
+    if args.iter().any(|arg| arg == "--ron-saves") {
+        world.add_resource(SaveBackend::Ron);
+    }
+    // `--explorer` keeps the save around when the player dies
+    if args.iter().any(|arg| arg == "--explorer") {
+        world.add_resource(ExplorerMode(true));
+    }
+
     main_menu(&mut world);
     game_loop(&mut world, &mut dispatcher);
 }
@@ -0,0 +1,110 @@
+use std::cmp;
+
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::generator::*;
+
+/// Recursively cuts the map in two, and puts a room in each of the pieces.
+/// Rooms never overlap and fill the map more evenly than `RoomsAndCorridors`.
+pub struct BspRooms {
+    /// Pieces smaller than this (in either direction) are not cut any further
+    pub min_leaf_size: i32,
+    pub room_min_size: i32,
+    pub max_depth: u32,
+}
+
+impl Default for BspRooms {
+    fn default() -> Self {
+        BspRooms {
+            min_leaf_size: 8,
+            room_min_size: 5,
+            max_depth: 5,
+        }
+    }
+}
+
+impl BspRooms {
+    fn split(&self, rng: &mut dyn RngCore, leaf: Rect, depth: u32, leaves: &mut Vec<Rect>) {
+        let can_split_h = leaf.width() >= self.min_leaf_size * 2;
+        let can_split_v = leaf.height() >= self.min_leaf_size * 2;
+        if depth >= self.max_depth || (!can_split_h && !can_split_v) {
+            leaves.push(leaf);
+            return;
+        }
+
+        // cut across the longer side, so the pieces don't get too thin
+        let split_h = if can_split_h && can_split_v {
+            leaf.width() > leaf.height() || (leaf.width() == leaf.height() && rng.gen())
+        } else {
+            can_split_h
+        };
+        let (first, second) = if split_h {
+            let w = rng.gen_range(self.min_leaf_size, leaf.width() - self.min_leaf_size + 1);
+            (
+                Rect::new(leaf.x1, leaf.y1, w, leaf.height()),
+                Rect::new(leaf.x1 + w, leaf.y1, leaf.width() - w, leaf.height()),
+            )
+        } else {
+            let h = rng.gen_range(self.min_leaf_size, leaf.height() - self.min_leaf_size + 1);
+            (
+                Rect::new(leaf.x1, leaf.y1, leaf.width(), h),
+                Rect::new(leaf.x1, leaf.y1 + h, leaf.width(), leaf.height() - h),
+            )
+        };
+        self.split(rng, first, depth + 1, leaves);
+        self.split(rng, second, depth + 1, leaves);
+    }
+
+    fn room_in(&self, rng: &mut dyn RngCore, leaf: &Rect) -> Rect {
+        let w = rng.gen_range(cmp::min(self.room_min_size, leaf.width()), leaf.width() + 1);
+        let h = rng.gen_range(
+            cmp::min(self.room_min_size, leaf.height()),
+            leaf.height() + 1,
+        );
+        let x = leaf.x1 + rng.gen_range(0, leaf.width() - w + 1);
+        let y = leaf.y1 + rng.gen_range(0, leaf.height() - h + 1);
+        Rect::new(x, y, w, h)
+    }
+}
+
+impl MapGenerator for BspRooms {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap {
+        let mut tiles = solid_tiles(width, height);
+
+        // leave the outermost ring of tiles solid
+        let mut leaves = vec![];
+        self.split(rng, Rect::new(0, 0, width - 1, height - 1), 0, &mut leaves);
+
+        // leaves come out in tree order, so consecutive rooms are always close to each other
+        let mut rooms: Vec<Rect> = vec![];
+        for leaf in &leaves {
+            let room = self.room_in(rng, leaf);
+            create_room(room, &mut tiles);
+            if let Some(previous) = rooms.last() {
+                connect(previous.center(), room.center(), rng.gen(), &mut tiles);
+            }
+            rooms.push(room);
+        }
+
+        // tiny maps might not have had space for any rooms at all
+        if rooms.is_empty() {
+            rooms.push(center_room(width, height, &mut tiles));
+        }
+
+        let (spawn_x, spawn_y) = rooms[0].center();
+        let spawn_point = Position {
+            x: spawn_x,
+            y: spawn_y,
+        };
+        let stairs = farthest_from(&tiles, &spawn_point);
+        GeneratedMap {
+            tiles,
+            spawn_point,
+            stairs,
+            spawn_regions: rooms.clone(),
+            rooms,
+            placed: vec![],
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::generator::*;
+use crate::resources::map::{Tile, Tiles};
+
+/// Random noise smoothed out by a cellular automaton, leaving natural looking caverns.
+/// Only the biggest cavern is kept, so everything is reachable.
+pub struct CellularCaves {
+    /// Chance of a tile starting out as wall
+    pub fill_chance: f64,
+    pub iterations: u32,
+    /// Size of the chunks monsters and items are spread over
+    pub region_size: i32,
+}
+
+impl Default for CellularCaves {
+    fn default() -> Self {
+        CellularCaves {
+            fill_chance: 0.45,
+            iterations: 5,
+            region_size: 10,
+        }
+    }
+}
+
+fn wall_neighbours(tiles: &Tiles, x: i32, y: i32) -> usize {
+    let mut walls = 0;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            if (dx, dy) != (0, 0) && tiles[(x + dx) as usize][(y + dy) as usize].blocked() {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
+
+impl MapGenerator for CellularCaves {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap {
+        let mut tiles = solid_tiles(width, height);
+        for x in 1..width - 1 {
+            for y in 1..height - 1 {
+                if !rng.gen_bool(self.fill_chance) {
+                    tiles[x as usize][y as usize] = Tile::floor();
+                }
+            }
+        }
+
+        // a tile becomes wall when most of its neighbours are
+        for _ in 0..self.iterations {
+            let mut next = tiles.clone();
+            for x in 1..width - 1 {
+                for y in 1..height - 1 {
+                    next[x as usize][y as usize] = if wall_neighbours(&tiles, x, y) >= 5 {
+                        Tile::wall()
+                    } else {
+                        Tile::floor()
+                    };
+                }
+            }
+            tiles = next;
+        }
+
+        // if the noise came out completely solid, at least make some room for the player
+        let spawn_point = largest_area(&tiles).unwrap_or_else(|| {
+            let (x, y) = center_room(width, height, &mut tiles).center();
+            Position { x, y }
+        });
+        wall_off_unreachable(&mut tiles, &spawn_point);
+        let stairs = farthest_from(&tiles, &spawn_point);
+        let spawn_regions = chunk_regions(&tiles, &spawn_point, self.region_size);
+
+        GeneratedMap {
+            tiles,
+            spawn_point,
+            stairs,
+            rooms: vec![],
+            spawn_regions,
+            placed: vec![],
+        }
+    }
+}
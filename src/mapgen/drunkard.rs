@@ -0,0 +1,70 @@
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::generator::*;
+use crate::resources::map::Tile;
+
+/// Stumbles around the map digging tunnels, until enough of it has been dug out.
+/// Every walker starts somewhere already dug, so the result is always connected.
+pub struct DrunkardsWalk {
+    /// Fraction of the map to dig out
+    pub floor_ratio: f32,
+    /// Number of steps each walker takes before passing out
+    pub lifetime: u32,
+    pub region_size: i32,
+}
+
+impl Default for DrunkardsWalk {
+    fn default() -> Self {
+        DrunkardsWalk {
+            floor_ratio: 0.4,
+            lifetime: 400,
+            region_size: 10,
+        }
+    }
+}
+
+impl MapGenerator for DrunkardsWalk {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap {
+        let mut tiles = solid_tiles(width, height);
+        let spawn_point = Position {
+            x: width / 2,
+            y: height / 2,
+        };
+        tiles[spawn_point.x as usize][spawn_point.y as usize] = Tile::floor();
+
+        let wanted = ((width - 2) * (height - 2)) as f32 * self.floor_ratio;
+        let mut dug = vec![spawn_point.clone()];
+        while (dug.len() as f32) < wanted {
+            let mut walker = dug[rng.gen_range(0, dug.len())].clone();
+            for _ in 0..self.lifetime {
+                match rng.gen_range(0, 4) {
+                    0 => walker.y -= 1,
+                    1 => walker.x += 1,
+                    2 => walker.y += 1,
+                    _ => walker.x -= 1,
+                }
+                // bounce off the edge of the map
+                walker.x = walker.x.max(1).min(width - 2);
+                walker.y = walker.y.max(1).min(height - 2);
+
+                let tile = &mut tiles[walker.x as usize][walker.y as usize];
+                if tile.blocked() {
+                    *tile = Tile::floor();
+                    dug.push(walker.clone());
+                }
+            }
+        }
+
+        let stairs = farthest_from(&tiles, &spawn_point);
+        let spawn_regions = chunk_regions(&tiles, &spawn_point, self.region_size);
+        GeneratedMap {
+            tiles,
+            spawn_point,
+            stairs,
+            rooms: vec![],
+            spawn_regions,
+            placed: vec![],
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::cmp;
+use std::collections::VecDeque;
+
+use rand::RngCore;
+
+use crate::components::Position;
+use crate::mapgen::Spawn;
+use crate::resources::map::{Tile, Tiles};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Rect {
+            x1: x,
+            y1: y,
+            x2: x + w,
+            y2: y + h,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.x2 - self.x1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.y2 - self.y1
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        let center_x = (self.x1 + self.x2) / 2;
+        let center_y = (self.y1 + self.y2) / 2;
+        (center_x, center_y)
+    }
+
+    pub fn intersects_with(&self, other: &Rect) -> bool {
+        (self.x1 <= other.x2)
+            && (self.x2 >= other.x1)
+            && (self.y1 <= other.y2)
+            && (self.y2 >= other.y1)
+    }
+
+    /// Whether the position is inside the room, walls excluded
+    pub fn contains(&self, position: &Position) -> bool {
+        position.x > self.x1 && position.x < self.x2 && position.y > self.y1 && position.y < self.y2
+    }
+}
+
+/// The layout of a level, without anything living in it
+pub struct GeneratedMap {
+    pub tiles: Tiles,
+    pub spawn_point: Position,
+    pub stairs: Position,
+    /// Actual rooms, which can get doors and terrain features. Empty for caves.
+    pub rooms: Vec<Rect>,
+    /// Areas to scatter monsters and items in. The first one holds the spawn point.
+    pub spawn_regions: Vec<Rect>,
+    /// Things that have to go in exactly the right spot, like the contents of a vault
+    pub placed: Vec<(Position, Spawn)>,
+}
+
+/// Lays out the tiles of a level. Implementations should only use the given RNG,
+/// so the same seed always makes the same map.
+pub trait MapGenerator {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap;
+}
+
+pub fn solid_tiles(width: i32, height: i32) -> Tiles {
+    vec![vec![Tile::wall(); height as usize]; width as usize]
+}
+
+pub fn create_room(room: Rect, map: &mut Tiles) {
+    for x in (room.x1 + 1)..room.x2 {
+        for y in (room.y1 + 1)..room.y2 {
+            map[x as usize][y as usize] = Tile::floor();
+        }
+    }
+}
+
+/// A small room in the middle of the map, for when a generator comes up empty
+pub fn center_room(width: i32, height: i32, map: &mut Tiles) -> Rect {
+    let room = Rect::new(width / 2 - 3, height / 2 - 3, 6, 6);
+    create_room(room, map);
+    room
+}
+
+pub fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Tiles) {
+    for x in cmp::min(x1, x2)..=cmp::max(x1, x2) {
+        map[x as usize][y as usize] = Tile::floor();
+    }
+}
+
+pub fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Tiles) {
+    for y in cmp::min(y1, y2)..=cmp::max(y1, y2) {
+        map[x as usize][y as usize] = Tile::floor();
+    }
+}
+
+/// Connect two points with an L-shaped tunnel, bending either way
+pub fn connect(from: (i32, i32), to: (i32, i32), horizontal_first: bool, map: &mut Tiles) {
+    let ((x1, y1), (x2, y2)) = (from, to);
+    if horizontal_first {
+        create_h_tunnel(x1, x2, y1, map);
+        create_v_tunnel(y1, y2, x2, map);
+    } else {
+        create_v_tunnel(y1, y2, x1, map);
+        create_h_tunnel(x1, x2, y2, map);
+    }
+}
+
+/// Dig the shortest tunnel from `from` to any open tile, going around the `avoid` areas.
+/// Returns false if there's no open tile to dig to.
+pub fn dig_to_open(tiles: &mut Tiles, from: &Position, avoid: &[Rect]) -> bool {
+    let width = tiles.len() as i32;
+    let height = tiles.first().map_or(0, Vec::len) as i32;
+    let mut came_from: Vec<Vec<Option<(i32, i32)>>> =
+        vec![vec![None; height as usize]; width as usize];
+    let mut queue = VecDeque::new();
+    queue.push_back((from.x, from.y));
+    came_from[from.x as usize][from.y as usize] = Some((from.x, from.y));
+
+    while let Some((x, y)) = queue.pop_front() {
+        if !tiles[x as usize][y as usize].blocked() {
+            // found one, walk back to the start
+            let mut current = (x, y);
+            while current != (from.x, from.y) {
+                current = came_from[current.0 as usize][current.1 as usize].unwrap();
+                tiles[current.0 as usize][current.1 as usize] = Tile::floor();
+            }
+            return true;
+        }
+        for (dx, dy) in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let (nx, ny) = (x + dx, y + dy);
+            // keep the outermost ring of the map solid
+            if nx < 1 || ny < 1 || nx >= width - 1 || ny >= height - 1 {
+                continue;
+            }
+            if came_from[nx as usize][ny as usize].is_some()
+                || avoid
+                    .iter()
+                    .any(|area| area.contains(&Position { x: nx, y: ny }))
+            {
+                continue;
+            }
+            came_from[nx as usize][ny as usize] = Some((x, y));
+            queue.push_back((nx, ny));
+        }
+    }
+    false
+}
+
+/// Anything that isn't solid rock can be walked through, doors included
+fn is_passable(tile: &Tile) -> bool {
+    !tile.blocked() || tile.as_door().is_some()
+}
+
+/// Walking distance from `start` to every tile, `None` where it can't be reached
+pub fn distance_map(tiles: &Tiles, start: &Position) -> Vec<Vec<Option<u32>>> {
+    let width = tiles.len();
+    let height = tiles.first().map_or(0, Vec::len);
+    let mut distances = vec![vec![None; height]; width];
+    let mut queue = VecDeque::new();
+    distances[start.x as usize][start.y as usize] = Some(0);
+    queue.push_back((start.x, start.y, 0));
+
+    while let Some((x, y, distance)) = queue.pop_front() {
+        for (dx, dy) in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let (ux, uy) = (nx as usize, ny as usize);
+            if distances[ux][uy].is_some() || !is_passable(&tiles[ux][uy]) {
+                continue;
+            }
+            distances[ux][uy] = Some(distance + 1);
+            queue.push_back((nx, ny, distance + 1));
+        }
+    }
+    distances
+}
+
+/// The reachable tile that's the longest walk away from `start`
+pub fn farthest_from(tiles: &Tiles, start: &Position) -> Position {
+    let distances = distance_map(tiles, start);
+    let mut farthest = (start.clone(), 0);
+    for (x, column) in distances.iter().enumerate() {
+        for (y, distance) in column.iter().enumerate() {
+            if let Some(distance) = *distance {
+                if distance > farthest.1 {
+                    let position = Position {
+                        x: x as i32,
+                        y: y as i32,
+                    };
+                    farthest = (position, distance);
+                }
+            }
+        }
+    }
+    farthest.0
+}
+
+/// Fill in every open tile that can't be reached from `start`
+pub fn wall_off_unreachable(tiles: &mut Tiles, start: &Position) {
+    let distances = distance_map(tiles, start);
+    for (x, column) in tiles.iter_mut().enumerate() {
+        for (y, tile) in column.iter_mut().enumerate() {
+            if distances[x][y].is_none() && !tile.blocked() {
+                *tile = Tile::wall();
+            }
+        }
+    }
+}
+
+/// Some tile of the biggest connected open area, or `None` if everything is solid
+pub fn largest_area(tiles: &Tiles) -> Option<Position> {
+    let mut visited = vec![vec![false; tiles.first().map_or(0, Vec::len)]; tiles.len()];
+    let mut largest: Option<(Position, usize)> = None;
+    for x in 0..tiles.len() {
+        for y in 0..tiles[x].len() {
+            if visited[x][y] || tiles[x][y].blocked() {
+                continue;
+            }
+            let start = Position {
+                x: x as i32,
+                y: y as i32,
+            };
+            let mut size = 0;
+            for (column, visited_column) in distance_map(tiles, &start).iter().zip(&mut visited) {
+                for (distance, visited) in column.iter().zip(visited_column.iter_mut()) {
+                    if distance.is_some() {
+                        *visited = true;
+                        size += 1;
+                    }
+                }
+            }
+            if largest.as_ref().map_or(true, |largest| size > largest.1) {
+                largest = Some((start, size));
+            }
+        }
+    }
+    largest.map(|largest| largest.0)
+}
+
+/// Split an irregular map into square chunks that have enough open space to put things in.
+/// The chunk holding `spawn_point` comes first.
+pub fn chunk_regions(tiles: &Tiles, spawn_point: &Position, size: i32) -> Vec<Rect> {
+    let width = tiles.len() as i32;
+    let height = tiles.first().map_or(0, Vec::len) as i32;
+    let mut regions = vec![];
+    // Neighbouring chunks share their edges, so every tile is inside exactly one of them
+    for x in (0..width - 2).step_by(size as usize - 1) {
+        for y in (0..height - 2).step_by(size as usize - 1) {
+            let region = Rect::new(
+                x,
+                y,
+                cmp::min(size, width - 1 - x),
+                cmp::min(size, height - 1 - y),
+            );
+            let open = ((region.x1 + 1)..region.x2)
+                .flat_map(|x| ((region.y1 + 1)..region.y2).map(move |y| (x, y)))
+                .filter(|&(x, y)| !tiles[x as usize][y as usize].blocked())
+                .count() as i32;
+            if region.contains(spawn_point) {
+                regions.insert(0, region);
+            } else if open * 4 >= (region.width() - 1) * (region.height() - 1) {
+                regions.push(region);
+            }
+        }
+    }
+    regions
+}
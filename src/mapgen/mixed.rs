@@ -0,0 +1,84 @@
+use rand::{Rng, RngCore};
+
+use crate::mapgen::{generator::*, RoomsAndCorridors};
+use crate::resources::map::Tile;
+
+/// Rooms and corridors, where some of the rooms have caved in
+pub struct Mixed {
+    pub rooms: RoomsAndCorridors,
+    /// Chance of a room turning into a cave
+    pub cave_chance: f64,
+    pub fill_chance: f64,
+}
+
+impl Default for Mixed {
+    fn default() -> Self {
+        Mixed {
+            rooms: RoomsAndCorridors::default(),
+            cave_chance: 0.5,
+            fill_chance: 0.4,
+        }
+    }
+}
+
+impl MapGenerator for Mixed {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap {
+        let mut map = self.rooms.generate(rng, width, height);
+        let mut caves = vec![];
+
+        for room in &map.rooms {
+            if room.contains(&map.spawn_point)
+                || room.contains(&map.stairs)
+                || !rng.gen_bool(self.cave_chance)
+            {
+                continue;
+            }
+
+            for x in (room.x1 + 1)..room.x2 {
+                for y in (room.y1 + 1)..room.y2 {
+                    if rng.gen_bool(self.fill_chance) {
+                        map.tiles[x as usize][y as usize] = Tile::wall();
+                    }
+                }
+            }
+            // smooth the rubble out a bit
+            for _ in 0..2 {
+                let before = map.tiles.clone();
+                for x in (room.x1 + 1)..room.x2 {
+                    for y in (room.y1 + 1)..room.y2 {
+                        let walls = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                            .iter()
+                            .filter(|(dx, dy)| {
+                                before[(x + dx) as usize][(y + dy) as usize].blocked()
+                            })
+                            .count();
+                        map.tiles[x as usize][y as usize] = if walls >= 3 {
+                            Tile::wall()
+                        } else {
+                            Tile::floor()
+                        };
+                    }
+                }
+            }
+            // tunnels always come in through the middle row or column,
+            // keeping those open keeps the level connected
+            let (center_x, center_y) = room.center();
+            create_h_tunnel(room.x1 + 1, room.x2 - 1, center_y, &mut map.tiles);
+            create_v_tunnel(room.y1 + 1, room.y2 - 1, center_x, &mut map.tiles);
+            caves.push(*room);
+        }
+
+        // tunnels between other rooms may have run through the caved in ones,
+        // dig out a new one wherever that cut a room off
+        for i in 1..map.rooms.len() {
+            let (x, y) = map.rooms[i].center();
+            if distance_map(&map.tiles, &map.spawn_point)[x as usize][y as usize].is_none() {
+                connect(map.rooms[i - 1].center(), (x, y), rng.gen(), &mut map.tiles);
+            }
+        }
+        wall_off_unreachable(&mut map.tiles, &map.spawn_point);
+        // caves don't get doors or terrain, but still get monsters
+        map.rooms.retain(|room| !caves.contains(room));
+        map
+    }
+}
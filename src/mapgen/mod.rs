@@ -0,0 +1,404 @@
+mod bsp;
+mod caves;
+mod drunkard;
+mod generator;
+mod mixed;
+mod prefab;
+mod rooms;
+mod spawn;
+mod validate;
+mod xp_level;
+
+pub use bsp::BspRooms;
+pub use caves::CellularCaves;
+pub use drunkard::DrunkardsWalk;
+pub use generator::{GeneratedMap, MapGenerator, Rect};
+pub use mixed::Mixed;
+pub use prefab::{all_prefabs, place_prefabs, Prefab};
+pub use rooms::RoomsAndCorridors;
+pub use spawn::{spawn, Spawn};
+pub use validate::{repair, validate, MapProblem};
+pub use xp_level::from_xp;
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use rand::{Rng, RngCore};
+use specs::{
+    saveload::{MarkedBuilder, U64Marker},
+    world::Builder,
+    World,
+};
+use tcod::colors;
+
+use crate::components::*;
+use crate::resources::{map::*, messages::Messages};
+use crate::rexpaint::XpFile;
+
+const MAX_ROOM_MONSTERS: i32 = 3;
+const MAX_ROOM_ITEMS: i32 = 2;
+const DOOR_CHANCE: f64 = 0.6;
+const LOCKED_DOOR_CHANCE: f64 = 0.15;
+const TRAP_CHANCE: f64 = 0.3;
+const MAX_GENERATION_ATTEMPTS: u32 = 10;
+const KEY_PLACEMENT_ATTEMPTS: u32 = 100;
+/// Caves sprawl, so they get more space than the regular levels
+const CAVE_WIDTH: i32 = 120;
+const CAVE_HEIGHT: i32 = 70;
+const LEVELS_DIR: &str = "assets/levels";
+
+fn place_objects(map: &Map, room: Rect, world: &mut World) {
+    // choose random number of monsters
+    let num_monsters = rand::thread_rng().gen_range(0, MAX_ROOM_MONSTERS + 1);
+
+    for _ in 0..num_monsters {
+        // choose random spot for this monster
+        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+        let position = Position { x, y };
+
+        // Check that we're not trying to place this monster at a location already occupied
+        // by something, or into lava.
+        if map.is_blocked(&position, &mut *world) || map[&position].kind.is_hazardous() {
+            continue;
+        }
+
+        spawn(
+            world,
+            Spawn::random_monster(&mut rand::thread_rng()),
+            position,
+        );
+    }
+
+    // choose random number of items
+    let num_items = rand::thread_rng().gen_range(0, MAX_ROOM_ITEMS + 1);
+
+    for _ in 0..num_items {
+        // choose random spot for this item
+        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+        let position = Position { x, y };
+
+        // only place it if the tile is not blocked (or about to swallow the item)
+        if !map.is_blocked(&position, &mut *world) && !map[&position].kind.is_hazardous() {
+            spawn(world, Spawn::random_item(&mut rand::thread_rng()), position);
+        }
+    }
+}
+
+/// Fill the middle of a room with water, lava or a chasm, or litter it with rubble.
+/// A ring of floor is always left along the walls, so the room can be walked around.
+fn place_terrain(room: Rect, tiles: &mut Tiles) {
+    let (x1, y1, x2, y2) = (room.x1 + 2, room.y1 + 2, room.x2 - 2, room.y2 - 2);
+    let mut fill = |x1: i32, y1: i32, x2: i32, y2: i32, kind: TileKind| {
+        for x in x1..=x2 {
+            for y in y1..=y2 {
+                tiles[x as usize][y as usize] = Tile::new(kind.clone());
+            }
+        }
+    };
+
+    let dice = rand::random::<f32>();
+    if dice < 0.15 {
+        // a pool, deep in the middle (15% chance)
+        fill(x1, y1, x2, y2, TileKind::ShallowWater);
+        fill(x1 + 1, y1 + 1, x2 - 1, y2 - 1, TileKind::DeepWater);
+    } else if dice < 0.15 + 0.07 {
+        // a lava pool (7% chance)
+        fill(x1, y1, x2, y2, TileKind::Lava);
+    } else if dice < 0.15 + 0.07 + 0.05 {
+        // a chasm down to the next level (5% chance)
+        fill(x1, y1, x2, y2, TileKind::Chasm);
+    } else if dice < 0.15 + 0.07 + 0.05 + 0.2 {
+        // a partly collapsed room (20% chance)
+        for _ in 0..rand::thread_rng().gen_range(3, 10) {
+            let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+            let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+            tiles[x as usize][y as usize] = Tile::new(TileKind::Rubble);
+        }
+    }
+}
+
+/// Put doors where tunnels enter rooms
+fn place_doors(rooms: &[Rect], tiles: &mut Tiles) {
+    for room in rooms {
+        let mut doorways = vec![];
+        for x in (room.x1 + 1)..room.x2 {
+            doorways.push((x, room.y1));
+            doorways.push((x, room.y2));
+        }
+        for y in (room.y1 + 1)..room.y2 {
+            doorways.push((room.x1, y));
+            doorways.push((room.x2, y));
+        }
+
+        for (x, y) in doorways {
+            if !is_doorway(x, y, tiles) || !rand::thread_rng().gen_bool(DOOR_CHANCE) {
+                continue;
+            }
+            let locked = rand::thread_rng().gen_bool(LOCKED_DOOR_CHANCE);
+            tiles[x as usize][y as usize] = Tile::door(locked);
+        }
+    }
+}
+
+/// A floor tile with walls on two opposite sides and floor on the other two
+fn is_doorway(x: i32, y: i32, tiles: &Tiles) -> bool {
+    let (width, height) = (tiles.len() as i32, tiles[0].len() as i32);
+    if x <= 0 || y <= 0 || x >= width - 1 || y >= height - 1 {
+        return false;
+    }
+    let is_floor = |x: i32, y: i32| tiles[x as usize][y as usize].kind == TileKind::Floor;
+    let is_wall = |x: i32, y: i32| tiles[x as usize][y as usize].kind == TileKind::Wall;
+    is_floor(x, y)
+        && ((is_wall(x - 1, y) && is_wall(x + 1, y) && is_floor(x, y - 1) && is_floor(x, y + 1))
+            || (is_wall(x, y - 1) && is_wall(x, y + 1) && is_floor(x - 1, y) && is_floor(x + 1, y)))
+}
+
+/// Keys go into the first room, so the player can always reach them before any locked door.
+/// If there's no room left there, the key goes on the nearest free tile the player can walk to
+/// without going through a door. Returns false if there's nowhere to put it.
+fn place_key(map: &Map, room: Rect, world: &mut World) -> bool {
+    let free = |position: &Position, world: &mut World| {
+        *position != map.spawn_point
+            && !map[position].kind.is_hazardous()
+            && !map.is_blocked(position, world)
+    };
+
+    for _ in 0..KEY_PLACEMENT_ATTEMPTS {
+        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+        let position = Position { x, y };
+        if free(&position, world) {
+            spawn(world, Spawn::Key, position);
+            return true;
+        }
+    }
+
+    let mut seen = vec![vec![false; map.height as usize]; map.width as usize];
+    let mut queue = VecDeque::new();
+    seen[map.spawn_point.x as usize][map.spawn_point.y as usize] = true;
+    queue.push_back(map.spawn_point.clone());
+    while let Some(position) = queue.pop_front() {
+        if free(&position, world) {
+            spawn(world, Spawn::Key, position);
+            return true;
+        }
+        for (dx, dy) in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let next = Position {
+                x: position.x + dx,
+                y: position.y + dy,
+            };
+            if !map.in_bounds(&next)
+                || seen[next.x as usize][next.y as usize]
+                || map[&next].blocked()
+            {
+                continue;
+            }
+            seen[next.x as usize][next.y as usize] = true;
+            queue.push_back(next);
+        }
+    }
+    false
+}
+
+fn place_trap(map: &Map, room: Rect, world: &mut World) {
+    let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+    let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+    let position = Position { x, y };
+    if map[&position].kind.is_hazardous() || map.is_blocked(&position, &mut *world) {
+        return;
+    }
+
+    let (kind, name) = match rand::thread_rng().gen_range(0, 5) {
+        0 => (TrapKind::Spikes, "spike trap"),
+        1 => (TrapKind::Teleport, "teleport trap"),
+        2 => (TrapKind::Alarm, "alarm trap"),
+        3 => (TrapKind::ConfusionGas, "confusion gas trap"),
+        _ => (TrapKind::Trapdoor, "trapdoor"),
+    };
+    world
+        .create_entity()
+        .with(position)
+        .with(Visual {
+            char: '^',
+            color: colors::LIGHT_RED,
+            always_visible: false,
+        })
+        .with(Name::new(name))
+        .with(Trap::hidden(kind))
+        .marked::<U64Marker>()
+        .build();
+}
+
+/// The first couple of levels are plain rooms, deeper down things get more varied.
+/// Returns the generator along with the size of map it should make.
+fn generator_for(depth: u32, rng: &mut dyn RngCore) -> (Box<dyn MapGenerator>, i32, i32) {
+    let choices = match depth {
+        0..=1 => 1,
+        2 => 2,
+        _ => 5,
+    };
+    let roll = rng.gen_range(0, choices);
+    let generator: Box<dyn MapGenerator> = match roll {
+        0 => Box::new(RoomsAndCorridors::default()),
+        1 => Box::new(BspRooms::default()),
+        2 => Box::new(CellularCaves::default()),
+        3 => Box::new(DrunkardsWalk::default()),
+        _ => Box::new(Mixed::default()),
+    };
+    match roll {
+        2 | 3 => (generator, CAVE_WIDTH, CAVE_HEIGHT),
+        _ => (generator, MAP_WIDTH, MAP_HEIGHT),
+    }
+}
+
+/// Generate maps until one passes validation, repairing them where possible.
+/// `None` if none of them work out.
+fn generate_valid_map(depth: u32, rng: &mut dyn RngCore) -> Option<GeneratedMap> {
+    for _ in 0..MAX_GENERATION_ATTEMPTS {
+        let (generator, width, height) = generator_for(depth, rng);
+        let mut generated = generator.generate(rng, width, height);
+        place_prefabs(&mut generated, depth, rng);
+        if let Err(MapProblem::Unreachable(_)) = validate(&generated) {
+            repair(&mut generated, rng);
+        }
+        if validate(&generated).is_ok() {
+            return Some(generated);
+        }
+    }
+    None
+}
+
+/// A generated map that passes validation.
+/// If nothing works out, the player gets a single room with the stairs in it.
+fn playable_map(depth: u32, rng: &mut dyn RngCore) -> GeneratedMap {
+    generate_valid_map(depth, rng).unwrap_or_else(fallback_map)
+}
+
+fn fallback_map() -> GeneratedMap {
+    let mut tiles = generator::solid_tiles(MAP_WIDTH, MAP_HEIGHT);
+    let room = generator::center_room(MAP_WIDTH, MAP_HEIGHT, &mut tiles);
+    let (x, y) = room.center();
+    GeneratedMap {
+        tiles,
+        spawn_point: Position { x, y },
+        stairs: Position { x: x + 1, y },
+        rooms: vec![room],
+        spawn_regions: vec![room],
+        placed: vec![],
+    }
+}
+
+/// Hand-made levels go here, named after the depth they replace, like `3.xp`
+fn level_file(depth: u32) -> PathBuf {
+    Path::new(LEVELS_DIR).join(format!("{}.xp", depth))
+}
+
+/// Make the level at `depth`, from its hand-made level file if there is one.
+/// A level file that can't be used gets a random level instead, and a message saying why.
+pub fn generate_map(world: &mut World, depth: u32) {
+    let level_file = level_file(depth);
+    let hand_made = if level_file.exists() {
+        match XpFile::open(&level_file).and_then(|file| from_xp(&file, &mut rand::thread_rng())) {
+            Ok(generated) => Some(generated),
+            Err(e) => {
+                world.write_resource::<Messages>().push(
+                    format!("Failed to load {}: {}", level_file.display(), e),
+                    colors::RED,
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let generated = hand_made.unwrap_or_else(|| playable_map(depth, &mut rand::thread_rng()));
+    populate(world, generated, depth);
+}
+
+/// Decorate the map, fill it with monsters and items, and make it the current one
+fn populate(world: &mut World, generated: GeneratedMap, depth: u32) {
+    let GeneratedMap {
+        mut tiles,
+        spawn_point,
+        stairs,
+        rooms,
+        spawn_regions,
+        placed,
+    } = generated;
+
+    // rooms with the player or the stairs in them are left plain,
+    // the rest may get some interesting terrain
+    for room in &rooms {
+        if !room.contains(&spawn_point) && !room.contains(&stairs) {
+            place_terrain(*room, &mut tiles);
+        }
+    }
+
+    // hang doors in the doorways
+    place_doors(&rooms, &mut tiles);
+    let locked_doors = tiles
+        .iter()
+        .flatten()
+        .filter(|tile| tile.as_door().map_or(false, |door| door.locked))
+        .count();
+
+    let mut map = Map {
+        width: tiles.len() as i32,
+        height: tiles[0].len() as i32,
+        tiles,
+        spawn_point,
+        depth,
+    };
+
+    // fill the prefabs first, so random monsters and items don't take their spots
+    for (position, what) in placed {
+        spawn(world, what, position);
+    }
+
+    // add some content to the map, such as monsters
+    for region in &spawn_regions {
+        place_objects(&map, *region, world);
+    }
+
+    // the starting area is always safe
+    for region in spawn_regions.iter().skip(1) {
+        if rand::thread_rng().gen_bool(TRAP_CHANCE) {
+            place_trap(&map, *region, world);
+        }
+    }
+
+    // make sure each locked door has a key
+    if let Some(start) = spawn_regions.first() {
+        for _ in 0..locked_doors {
+            if place_key(&map, *start, world) {
+                continue;
+            }
+            // no key for it, so the door can't stay locked
+            if let Some(door) = map
+                .tiles
+                .iter_mut()
+                .flatten()
+                .find(|tile| tile.as_door().map_or(false, |door| door.locked))
+            {
+                door.open_door();
+                door.close_door();
+            }
+        }
+    }
+
+    world
+        .create_entity()
+        .with(stairs)
+        .with(Visual {
+            char: '>',
+            color: colors::WHITE,
+            always_visible: true,
+        })
+        .with(Name::new("stairs"))
+        .marked::<U64Marker>()
+        .build();
+
+    world.add_resource(map);
+}
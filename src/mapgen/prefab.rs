@@ -0,0 +1,260 @@
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::{generator::*, Spawn};
+use crate::resources::map::{Tile, TileKind};
+
+const PLACEMENT_ATTEMPTS: u32 = 30;
+const LEGEND: &str = " #.+=~w%:oTM!?)*k";
+
+/// The prefab files, baked into the binary
+const PREFAB_FILES: &[(&str, &str)] = &[
+    (
+        "treasure_vault.txt",
+        include_str!("../../assets/prefabs/treasure_vault.txt"),
+    ),
+    (
+        "guard_post.txt",
+        include_str!("../../assets/prefabs/guard_post.txt"),
+    ),
+    (
+        "flooded_shrine.txt",
+        include_str!("../../assets/prefabs/flooded_shrine.txt"),
+    ),
+    (
+        "troll_den.txt",
+        include_str!("../../assets/prefabs/troll_den.txt"),
+    ),
+];
+
+/// A hand-drawn room. The file format is a few `key: value` header lines (`name`, `chance`
+/// and `min_depth`), an empty line, then the room itself, using this legend:
+///
+/// ```text
+/// ' '  leave the map as it is      'o'  orc
+/// '#'  wall                        'T'  troll
+/// '.'  floor                       'M'  random monster
+/// '+'  door                        '!'  healing potion
+/// '='  locked door                 '?'  random scroll
+/// '~'  shallow water               ')'  dagger
+/// 'w'  deep water                  '*'  random item
+/// '%'  lava                        'k'  key
+/// ':'  rubble
+/// ```
+///
+/// Doors on the edge of the drawing are the entrances, and get connected to the rest of the map.
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    pub name: String,
+    /// Chance of it appearing on any given level
+    pub chance: f64,
+    pub min_depth: u32,
+    rows: Vec<Vec<char>>,
+}
+
+impl Prefab {
+    pub fn parse(text: &str) -> Result<Prefab, String> {
+        let mut lines = text.lines();
+        let mut name = None;
+        let mut chance = 1.0;
+        let mut min_depth = 1;
+
+        for line in &mut lines {
+            if line.trim().is_empty() {
+                break;
+            }
+            let mut parts = line.splitn(2, ':');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => return Err(format!("Bad header line: {}", line)),
+            };
+            match key {
+                "name" => name = Some(value.to_string()),
+                "chance" => {
+                    chance = value
+                        .parse()
+                        .ok()
+                        .filter(|&chance| chance >= 0.0 && chance <= 1.0)
+                        .ok_or_else(|| format!("Bad chance, it should be 0 to 1: {}", value))?
+                }
+                "min_depth" => {
+                    min_depth = value
+                        .parse()
+                        .map_err(|_| format!("Bad min_depth: {}", value))?
+                }
+                _ => return Err(format!("Unknown header: {}", key)),
+            }
+        }
+
+        let mut rows: Vec<Vec<char>> = lines.map(|line| line.chars().collect()).collect();
+        while rows.last().map_or(false, |row| row.is_empty()) {
+            rows.pop();
+        }
+        if rows.is_empty() {
+            return Err("The drawing is empty".to_string());
+        }
+        // pad the rows, so the drawing is a proper rectangle
+        let width = rows.iter().map(Vec::len).max().unwrap();
+        for row in &mut rows {
+            row.resize(width, ' ');
+        }
+        if let Some(c) = rows.iter().flatten().find(|c| !LEGEND.contains(**c)) {
+            return Err(format!("Unknown character in drawing: {:?}", c));
+        }
+
+        Ok(Prefab {
+            name: name.ok_or_else(|| "Missing name".to_string())?,
+            chance,
+            min_depth,
+            rows,
+        })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.rows[0].len() as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.rows.len() as i32
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (i32, i32, char)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, &c)| (x as i32, y as i32, c))
+        })
+    }
+
+    /// The prefab at `x`, `y` with a ring of rock around it
+    fn bounds(&self, x: i32, y: i32) -> Rect {
+        Rect::new(x - 1, y - 1, self.width() + 1, self.height() + 1)
+    }
+
+    /// Whether the prefab, plus a ring of rock around it, would only replace solid rock,
+    /// and stays clear of other prefabs
+    fn fits(&self, map: &GeneratedMap, x: i32, y: i32, taken: &[Rect]) -> bool {
+        let (width, height) = (map.tiles.len() as i32, map.tiles[0].len() as i32);
+        if x < 2 || y < 2 || x + self.width() > width - 2 || y + self.height() > height - 2 {
+            return false;
+        }
+        let bounds = self.bounds(x, y);
+        if taken.iter().any(|other| other.intersects_with(&bounds)) {
+            return false;
+        }
+        ((x - 1)..=(x + self.width())).all(|tx| {
+            ((y - 1)..=(y + self.height())).all(|ty| map.tiles[tx as usize][ty as usize].blocked())
+        })
+    }
+
+    /// Draw the prefab onto the map with its top left corner at `x`, `y`,
+    /// and dig tunnels from its entrances to the nearest open space
+    fn stamp(&self, map: &mut GeneratedMap, x: i32, y: i32, taken: &[Rect], rng: &mut dyn RngCore) {
+        let mut entrances = vec![];
+        for (dx, dy, c) in self.cells() {
+            let (tile, spawn) = match cell(c, rng) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            let position = Position {
+                x: x + dx,
+                y: y + dy,
+            };
+            map.tiles[position.x as usize][position.y as usize] = tile;
+            if let Some(spawn) = spawn {
+                map.placed.push((position, spawn));
+            }
+
+            // the tile just outside a door on the edge
+            if c == '+' || c == '=' {
+                let outside = match (dx, dy) {
+                    (0, _) => Some((-1, 0)),
+                    (_, 0) => Some((0, -1)),
+                    _ if dx == self.width() - 1 => Some((1, 0)),
+                    _ if dy == self.height() - 1 => Some((0, 1)),
+                    _ => None,
+                };
+                if let Some((ox, oy)) = outside {
+                    entrances.push(Position {
+                        x: x + dx + ox,
+                        y: y + dy + oy,
+                    });
+                }
+            }
+        }
+
+        // don't dig into this or any other prefab through the walls
+        let mut avoid = taken.to_vec();
+        avoid.push(self.bounds(x, y));
+        for entrance in entrances {
+            dig_to_open(&mut map.tiles, &entrance, &avoid);
+        }
+    }
+
+    /// Try to find room for the prefab somewhere on the map, away from the `taken` areas.
+    /// Returns where it ended up, if it fit anywhere.
+    pub fn place(
+        &self,
+        map: &mut GeneratedMap,
+        rng: &mut dyn RngCore,
+        taken: &[Rect],
+    ) -> Option<Rect> {
+        let (width, height) = (map.tiles.len() as i32, map.tiles[0].len() as i32);
+        if width < self.width() + 4 || height < self.height() + 4 {
+            return None;
+        }
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let x = rng.gen_range(2, width - self.width() - 1);
+            let y = rng.gen_range(2, height - self.height() - 1);
+            if self.fits(map, x, y, taken) {
+                self.stamp(map, x, y, taken, rng);
+                return Some(self.bounds(x, y));
+            }
+        }
+        None
+    }
+}
+
+/// The tile (and maybe entity) a character in a prefab drawing stands for,
+/// `None` if the map should be left alone. Random monsters and items are rolled with `rng`.
+pub fn cell(c: char, rng: &mut dyn RngCore) -> Option<(Tile, Option<Spawn>)> {
+    let floor_with = |spawn| Some((Tile::floor(), Some(spawn)));
+    match c {
+        '#' => Some((Tile::wall(), None)),
+        '.' => Some((Tile::floor(), None)),
+        '+' => Some((Tile::door(false), None)),
+        '=' => Some((Tile::door(true), None)),
+        '~' => Some((Tile::new(TileKind::ShallowWater), None)),
+        'w' => Some((Tile::new(TileKind::DeepWater), None)),
+        '%' => Some((Tile::new(TileKind::Lava), None)),
+        ':' => Some((Tile::new(TileKind::Rubble), None)),
+        'o' => floor_with(Spawn::Orc),
+        'T' => floor_with(Spawn::Troll),
+        'M' => floor_with(Spawn::random_monster(rng)),
+        '!' => floor_with(Spawn::HealingPotion),
+        '?' => floor_with(Spawn::random_scroll(rng)),
+        ')' => floor_with(Spawn::Dagger),
+        '*' => floor_with(Spawn::random_item(rng)),
+        'k' => floor_with(Spawn::Key),
+        _ => None,
+    }
+}
+
+pub fn all_prefabs() -> Vec<Prefab> {
+    PREFAB_FILES
+        .iter()
+        .map(|(file, text)| {
+            Prefab::parse(text).unwrap_or_else(|e| panic!("Invalid prefab {}: {}", file, e))
+        })
+        .collect()
+}
+
+/// Stamp some of the prefabs that are allowed at this depth onto the map
+pub fn place_prefabs(map: &mut GeneratedMap, depth: u32, rng: &mut dyn RngCore) {
+    let mut taken = vec![];
+    for prefab in all_prefabs() {
+        if depth >= prefab.min_depth && rng.gen_bool(prefab.chance) {
+            taken.extend(prefab.place(map, rng, &taken));
+        }
+    }
+}
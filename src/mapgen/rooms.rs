@@ -0,0 +1,86 @@
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::generator::*;
+
+/// The classic: rectangular rooms thrown at the map, each connected to the one before it
+pub struct RoomsAndCorridors {
+    pub max_rooms: i32,
+    pub room_min_size: i32,
+    pub room_max_size: i32,
+}
+
+impl Default for RoomsAndCorridors {
+    fn default() -> Self {
+        RoomsAndCorridors {
+            max_rooms: 30,
+            room_min_size: 6,
+            room_max_size: 10,
+        }
+    }
+}
+
+impl MapGenerator for RoomsAndCorridors {
+    fn generate(&self, rng: &mut dyn RngCore, width: i32, height: i32) -> GeneratedMap {
+        let mut tiles = solid_tiles(width, height);
+        let mut rooms: Vec<Rect> = vec![];
+
+        for _ in 0..self.max_rooms {
+            // random width and height
+            let w = rng.gen_range(self.room_min_size, self.room_max_size + 1);
+            let h = rng.gen_range(self.room_min_size, self.room_max_size + 1);
+            if w >= width || h >= height {
+                continue;
+            }
+            // random position without going out of the boundaries of the map
+            let x = rng.gen_range(0, width - w);
+            let y = rng.gen_range(0, height - h);
+
+            let new_room = Rect::new(x, y, w, h);
+
+            // run through the other rooms and see if they intersect with this one
+            let failed = rooms
+                .iter()
+                .any(|other_room| new_room.intersects_with(other_room));
+
+            if !failed {
+                // this means there are no intersections, so this room is valid
+
+                // "paint" it to the map's tiles
+                create_room(new_room, &mut tiles);
+
+                // all rooms after the first:
+                // connect it to the previous room with a tunnel
+                if let Some(previous) = rooms.last() {
+                    connect(previous.center(), new_room.center(), rng.gen(), &mut tiles);
+                }
+
+                // finally, append the new room to the list
+                rooms.push(new_room);
+            }
+        }
+
+        // tiny maps might not have had space for any rooms at all
+        if rooms.is_empty() {
+            rooms.push(center_room(width, height, &mut tiles));
+        }
+
+        // the player starts in the first room, the stairs are in the center of the last one
+        let (spawn_x, spawn_y) = rooms[0].center();
+        let (stairs_x, stairs_y) = rooms[rooms.len() - 1].center();
+        GeneratedMap {
+            tiles,
+            spawn_point: Position {
+                x: spawn_x,
+                y: spawn_y,
+            },
+            stairs: Position {
+                x: stairs_x,
+                y: stairs_y,
+            },
+            spawn_regions: rooms.clone(),
+            rooms,
+            placed: vec![],
+        }
+    }
+}
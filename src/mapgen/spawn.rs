@@ -0,0 +1,323 @@
+use rand::{Rng, RngCore};
+use specs::{
+    saveload::{MarkedBuilder, U64Marker},
+    world::Builder,
+    World,
+};
+use tcod::colors;
+
+use crate::components::*;
+use crate::resources::identification::Identification;
+
+/// Everything the map generator knows how to put into the world
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spawn {
+    Orc,
+    Troll,
+    Dagger,
+    HealingPotion,
+    LightningScroll,
+    ConfusionScroll,
+    FireballScroll,
+    Bow,
+    Sling,
+    Arrows,
+    SlingStones,
+    Key,
+}
+
+impl Spawn {
+    pub fn random_monster(rng: &mut dyn RngCore) -> Spawn {
+        if rng.gen::<f32>() < 0.8 {
+            // 80% chance of getting an orc
+            Spawn::Orc
+        } else {
+            Spawn::Troll
+        }
+    }
+
+    pub fn random_item(rng: &mut dyn RngCore) -> Spawn {
+        let dice = rng.gen::<f32>();
+        if dice < 0.03 {
+            // a throwing dagger (3% chance)
+            Spawn::Dagger
+        } else if dice < 0.6 {
+            // a healing potion (57% chance)
+            Spawn::HealingPotion
+        } else if dice < 0.6 + 0.08 {
+            // a lightning bolt scroll (8% chance)
+            Spawn::LightningScroll
+        } else if dice < 0.6 + 0.08 + 0.08 {
+            // a confusion scroll (8% chance)
+            Spawn::ConfusionScroll
+        } else if dice < 0.6 + 0.08 + 0.08 + 0.08 {
+            // a fireball scroll (8% chance)
+            Spawn::FireballScroll
+        } else if dice < 0.6 + 0.08 + 0.08 + 0.08 + 0.03 {
+            // a bow (3% chance)
+            Spawn::Bow
+        } else if dice < 0.6 + 0.08 + 0.08 + 0.08 + 0.03 + 0.03 {
+            // a sling (3% chance)
+            Spawn::Sling
+        } else if dice < 0.6 + 0.08 + 0.08 + 0.08 + 0.03 + 0.03 + 0.05 {
+            // a bundle of arrows (5% chance)
+            Spawn::Arrows
+        } else {
+            // a pile of sling stones (5% chance)
+            Spawn::SlingStones
+        }
+    }
+
+    pub fn random_scroll(rng: &mut dyn RngCore) -> Spawn {
+        match rng.gen_range(0, 3) {
+            0 => Spawn::LightningScroll,
+            1 => Spawn::ConfusionScroll,
+            _ => Spawn::FireballScroll,
+        }
+    }
+}
+
+/// Unidentified items look different in every game
+fn item_color(world: &World, item: &Item, default: colors::Color) -> colors::Color {
+    world
+        .read_resource::<Identification>()
+        .color_of(item)
+        .unwrap_or(default)
+}
+
+pub fn spawn(world: &mut World, what: Spawn, position: Position) {
+    match what {
+        Spawn::Orc => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Velocity::new())
+                .with(Visual {
+                    char: 'o',
+                    color: colors::DESATURATED_GREEN,
+                    always_visible: false,
+                })
+                .with(Collider::new())
+                .with(Name::new("orc"))
+                .with(Living {
+                    alive: true,
+                    max_hp: 10,
+                    hp: 10,
+                    defense: 0,
+                    last_damage: None,
+                })
+                .with(Power(3))
+                .with(Ai::Basic)
+                .with(Action::noop())
+                .with(Energy::new())
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::Troll => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Velocity::new())
+                .with(Visual {
+                    char: 'T',
+                    color: colors::DARKER_GREEN,
+                    always_visible: false,
+                })
+                .with(Collider::new())
+                .with(Name::new("troll"))
+                .with(Living {
+                    alive: true,
+                    max_hp: 16,
+                    hp: 16,
+                    defense: 1,
+                    last_damage: None,
+                })
+                .with(Power(4))
+                .with(Ai::Basic)
+                .with(Energy::new())
+                .with(Action::noop())
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::Dagger => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: ')',
+                    color: colors::LIGHTER_GREY,
+                    always_visible: false,
+                })
+                .with(Name::new("dagger"))
+                .with(Weight(1))
+                .with(Item::Weapon)
+                .with(ThrownDamage(4))
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::HealingPotion => {
+            let color = item_color(world, &Item::Heal, colors::VIOLET);
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: '!',
+                    color,
+                    always_visible: false,
+                })
+                .with(Name::new("healing potion"))
+                .with(Weight(2))
+                .with(Item::Heal)
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::LightningScroll => {
+            let color = item_color(world, &Item::Lightning, colors::LIGHT_YELLOW);
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: '#',
+                    color,
+                    always_visible: false,
+                })
+                .with(Name::new("scroll of lightning bolt"))
+                .with(Weight(1))
+                .with(Item::Lightning)
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::ConfusionScroll => {
+            let color = item_color(world, &Item::Confuse, colors::LIGHT_BLUE);
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: '#',
+                    color,
+                    always_visible: false,
+                })
+                .with(Name::new("scroll of confusion"))
+                .with(Weight(1))
+                .with(Item::Confuse)
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::FireballScroll => {
+            let color = item_color(world, &Item::Fireball, colors::DARK_RED);
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: '#',
+                    color,
+                    always_visible: false,
+                })
+                .with(Name::new("scroll of fireball"))
+                .with(Weight(1))
+                .with(Item::Fireball)
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::Bow => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: ')',
+                    color: colors::SEPIA,
+                    always_visible: false,
+                })
+                .with(Name::new("bow"))
+                .with(Weight(3))
+                .with(Item::Weapon)
+                .with(Launcher {
+                    ammo: AmmoKind::Arrow,
+                    damage: 3,
+                    range: 10,
+                })
+                .with(ThrownDamage(2))
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::Sling => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: ')',
+                    color: colors::LIGHT_SEPIA,
+                    always_visible: false,
+                })
+                .with(Name::new("sling"))
+                .with(Weight(1))
+                .with(Item::Weapon)
+                .with(Launcher {
+                    ammo: AmmoKind::Stone,
+                    damage: 1,
+                    range: 6,
+                })
+                .with(ThrownDamage(1))
+                .marked::<U64Marker>()
+                .build();
+        }
+        Spawn::Arrows => {
+            for _ in 0..rand::thread_rng().gen_range(3, 8) {
+                world
+                    .create_entity()
+                    .with(position.clone())
+                    .with(Visual {
+                        char: '/',
+                        color: colors::SEPIA,
+                        always_visible: false,
+                    })
+                    .with(Name::new("arrow"))
+                    .with(Weight(0))
+                    .with(Item::Ammo)
+                    .with(Ammo {
+                        kind: AmmoKind::Arrow,
+                        damage: 2,
+                    })
+                    .with(ThrownDamage(1))
+                    .marked::<U64Marker>()
+                    .build();
+            }
+        }
+        Spawn::SlingStones => {
+            for _ in 0..rand::thread_rng().gen_range(3, 8) {
+                world
+                    .create_entity()
+                    .with(position.clone())
+                    .with(Visual {
+                        char: '*',
+                        color: colors::GREY,
+                        always_visible: false,
+                    })
+                    .with(Name::new("sling stone"))
+                    .with(Weight(0))
+                    .with(Item::Ammo)
+                    .with(Ammo {
+                        kind: AmmoKind::Stone,
+                        damage: 2,
+                    })
+                    .with(ThrownDamage(2))
+                    .marked::<U64Marker>()
+                    .build();
+            }
+        }
+        Spawn::Key => {
+            world
+                .create_entity()
+                .with(position)
+                .with(Visual {
+                    char: '-',
+                    color: colors::GOLD,
+                    always_visible: false,
+                })
+                .with(Name::new("key"))
+                .with(Item::Key)
+                .marked::<U64Marker>()
+                .build();
+        }
+    }
+}
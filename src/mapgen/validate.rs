@@ -0,0 +1,184 @@
+use rand::{Rng, RngCore};
+
+use crate::components::Position;
+use crate::mapgen::generator::*;
+
+/// Why a generated map can't be played
+#[derive(Debug, PartialEq)]
+pub enum MapProblem {
+    /// The player would start inside a wall
+    SpawnBlocked,
+    /// There's nowhere to put monsters and items, or the first region isn't where the player is
+    BadSpawnRegions,
+    /// This open tile can't be walked to from the spawn point
+    Unreachable(Position),
+    /// This tile on the edge of the map isn't solid, so something could walk off the map
+    OpenBorder(Position),
+}
+
+/// Check that the map is walled in, and that the player can get from the spawn point to the
+/// stairs, and to every open tile on the map, which includes everywhere a monster or item
+/// could end up.
+pub fn validate(map: &GeneratedMap) -> Result<(), MapProblem> {
+    let (width, height) = (map.tiles.len(), map.tiles.first().map_or(0, Vec::len));
+    for (x, column) in map.tiles.iter().enumerate() {
+        for (y, tile) in column.iter().enumerate() {
+            let on_edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if on_edge && (!tile.blocked() || tile.as_door().is_some()) {
+                return Err(MapProblem::OpenBorder(Position {
+                    x: x as i32,
+                    y: y as i32,
+                }));
+            }
+        }
+    }
+
+    let spawn = &map.spawn_point;
+    if map.tiles[spawn.x as usize][spawn.y as usize].blocked() {
+        return Err(MapProblem::SpawnBlocked);
+    }
+    if map
+        .spawn_regions
+        .first()
+        .map_or(true, |region| !region.contains(spawn))
+    {
+        return Err(MapProblem::BadSpawnRegions);
+    }
+
+    let distances = distance_map(&map.tiles, spawn);
+    if distances[map.stairs.x as usize][map.stairs.y as usize].is_none() {
+        return Err(MapProblem::Unreachable(map.stairs.clone()));
+    }
+    for (x, column) in map.tiles.iter().enumerate() {
+        for (y, tile) in column.iter().enumerate() {
+            if !tile.blocked() && distances[x][y].is_none() {
+                return Err(MapProblem::Unreachable(Position {
+                    x: x as i32,
+                    y: y as i32,
+                }));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fix reachability problems: dig a tunnel to the stairs if needed, then fill in whatever
+/// other pockets are left over. Can't do anything about a blocked spawn point.
+pub fn repair(map: &mut GeneratedMap, rng: &mut dyn RngCore) {
+    let distances = distance_map(&map.tiles, &map.spawn_point);
+    if distances[map.stairs.x as usize][map.stairs.y as usize].is_none() {
+        let spawn = (map.spawn_point.x, map.spawn_point.y);
+        let stairs = (map.stairs.x, map.stairs.y);
+        connect(spawn, stairs, rng.gen(), &mut map.tiles);
+    }
+    wall_off_unreachable(&mut map.tiles, &map.spawn_point);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::mapgen::{
+        generate_valid_map, place_prefabs, BspRooms, CellularCaves, DrunkardsWalk, Mixed,
+        RoomsAndCorridors, CAVE_HEIGHT, CAVE_WIDTH,
+    };
+    use crate::resources::map::{MAP_HEIGHT, MAP_WIDTH};
+
+    const SEEDS: u64 = 300;
+
+    /// Generate a map for every seed, repair it like `playable_map` does, and check the result
+    fn check_generator<G: MapGenerator>(generator: G, width: i32, height: i32) {
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut map = generator.generate(&mut rng, width, height);
+            place_prefabs(&mut map, 10, &mut rng);
+            if let Err(MapProblem::Unreachable(_)) = validate(&map) {
+                repair(&mut map, &mut rng);
+            }
+            assert_eq!(validate(&map), Ok(()), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn rooms_and_corridors() {
+        check_generator(RoomsAndCorridors::default(), MAP_WIDTH, MAP_HEIGHT);
+    }
+
+    #[test]
+    fn bsp_rooms() {
+        check_generator(BspRooms::default(), MAP_WIDTH, MAP_HEIGHT);
+    }
+
+    #[test]
+    fn cellular_caves() {
+        check_generator(CellularCaves::default(), CAVE_WIDTH, CAVE_HEIGHT);
+    }
+
+    #[test]
+    fn drunkards_walk() {
+        check_generator(DrunkardsWalk::default(), CAVE_WIDTH, CAVE_HEIGHT);
+    }
+
+    #[test]
+    fn mixed() {
+        check_generator(Mixed::default(), MAP_WIDTH, MAP_HEIGHT);
+    }
+
+    /// Every depth should get a map from its generators, not the single room `playable_map`
+    /// falls back on, which passes validation no matter what
+    #[test]
+    fn generators_make_valid_maps_at_every_depth() {
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let depth = 1 + (seed % 10) as u32;
+            let map = generate_valid_map(depth, &mut rng);
+            assert!(map.is_some(), "seed {}, depth {}", seed, depth);
+        }
+    }
+
+    /// Two rooms with nothing between them, the player in the first one
+    fn two_rooms(stairs: Position) -> GeneratedMap {
+        let mut tiles = solid_tiles(30, 10);
+        let first = Rect::new(1, 1, 6, 6);
+        let second = Rect::new(15, 1, 6, 6);
+        create_room(first, &mut tiles);
+        create_room(second, &mut tiles);
+        GeneratedMap {
+            tiles,
+            spawn_point: Position { x: 3, y: 3 },
+            stairs,
+            rooms: vec![first, second],
+            spawn_regions: vec![first, second],
+            placed: vec![],
+        }
+    }
+
+    #[test]
+    fn unreachable_stairs() {
+        let map = two_rooms(Position { x: 17, y: 3 });
+        assert_eq!(
+            validate(&map),
+            Err(MapProblem::Unreachable(Position { x: 17, y: 3 }))
+        );
+    }
+
+    #[test]
+    fn unreachable_room() {
+        let map = two_rooms(Position { x: 4, y: 3 });
+        assert_eq!(
+            validate(&map),
+            Err(MapProblem::Unreachable(Position { x: 16, y: 2 }))
+        );
+    }
+
+    #[test]
+    fn repair_connects_rooms() {
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut map = two_rooms(Position { x: 17, y: 3 });
+            repair(&mut map, &mut rng);
+            assert_eq!(validate(&map), Ok(()), "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use rand::RngCore;
+
+use crate::components::Position;
+use crate::mapgen::{
+    generator::{distance_map, solid_tiles, Rect},
+    prefab::cell,
+    validate::validate,
+    GeneratedMap,
+};
+use crate::resources::map::{Tile, Tiles};
+use crate::rexpaint::{XpError, XpFile};
+
+/// How far from the player's starting point keys for the locked doors can end up
+const SPAWN_REGION_RADIUS: u32 = 6;
+
+/// Turn a REXPaint drawing into a level. The first layer holds the tiles and the optional
+/// second layer holds what goes on them, both using the same legend as prefabs
+/// (see `Prefab`), plus '@' for where the player starts and '>' for the stairs.
+/// Anything else on the first layer is solid rock, and so is the edge of the map.
+///
+/// The area around the player's starting point is treated like the first room of a random
+/// level, so it gets the keys for any locked doors, and a few monsters and items.
+/// 'M', '?' and '*' are rolled with `rng`.
+pub fn from_xp(file: &XpFile, rng: &mut dyn RngCore) -> Result<GeneratedMap, XpError> {
+    let (width, height) = (file.width(), file.height());
+    let mut tiles = solid_tiles(width, height);
+    let mut placed = vec![];
+    let mut spawn_point = None;
+    let mut stairs = None;
+
+    for x in 0..width {
+        for y in 0..height {
+            let position = Position { x, y };
+            let glyphs = file
+                .layers
+                .iter()
+                .take(2)
+                .map(|layer| layer.get(x, y).and_then(|cell| cell.ascii()));
+            for (layer, glyph) in glyphs.enumerate() {
+                match (layer, glyph) {
+                    (_, Some('@')) => {
+                        tiles[x as usize][y as usize] = Tile::floor();
+                        spawn_point = Some(position.clone());
+                    }
+                    (_, Some('>')) => {
+                        tiles[x as usize][y as usize] = Tile::floor();
+                        stairs = Some(position.clone());
+                    }
+                    (_, Some(glyph)) => {
+                        if let Some((tile, spawn)) = cell(glyph, rng) {
+                            // only the first layer decides the tiles
+                            if layer == 0 {
+                                tiles[x as usize][y as usize] = tile;
+                            }
+                            if let Some(spawn) = spawn {
+                                placed.push((position.clone(), spawn));
+                            }
+                        }
+                    }
+                    (_, None) => (),
+                }
+            }
+        }
+    }
+
+    // nothing gets to walk off the edge of the map
+    let on_edge = |x: i32, y: i32| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+    for x in 0..width {
+        for y in 0..height {
+            if on_edge(x, y) {
+                tiles[x as usize][y as usize] = Tile::wall();
+            }
+        }
+    }
+    placed.retain(|(position, _)| !on_edge(position.x, position.y));
+
+    let spawn_point =
+        spawn_point.ok_or_else(|| XpError::Format("no '@' for the player".to_string()))?;
+    let stairs = stairs.ok_or_else(|| XpError::Format("no '>' for the stairs".to_string()))?;
+    let spawn_region = spawn_region(&tiles, &spawn_point);
+    let map = GeneratedMap {
+        tiles,
+        spawn_point,
+        stairs,
+        rooms: vec![],
+        spawn_regions: vec![spawn_region],
+        placed,
+    };
+    validate(&map)
+        .map_err(|problem| XpError::Format(format!("the level can't be played: {:?}", problem)))?;
+    Ok(map)
+}
+
+/// The smallest area holding every tile within a short walk of the spawn point
+fn spawn_region(tiles: &Tiles, spawn_point: &Position) -> Rect {
+    let distances = distance_map(tiles, spawn_point);
+    let (mut x1, mut y1, mut x2, mut y2) =
+        (spawn_point.x, spawn_point.y, spawn_point.x, spawn_point.y);
+    for (x, column) in distances.iter().enumerate() {
+        for (y, distance) in column.iter().enumerate() {
+            if distance.map_or(false, |distance| distance <= SPAWN_REGION_RADIUS) {
+                let (x, y) = (x as i32, y as i32);
+                x1 = x1.min(x);
+                y1 = y1.min(y);
+                x2 = x2.max(x);
+                y2 = y2.max(y);
+            }
+        }
+    }
+    // rects leave out their edges, which is where the walls of a room would be
+    Rect::new(x1 - 1, y1 - 1, x2 - x1 + 2, y2 - y1 + 2)
+}
@@ -0,0 +1,99 @@
+//! What's left after the player dies: a summary for the death screen, and a morgue file with
+//! the whole story
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use specs::{Join, World};
+
+use crate::{
+    components::{Inventory, Item, Living, Name, Player},
+    export::{objects, to_ascii},
+    resources::{
+        identification::Identification,
+        map::Map,
+        messages::Messages,
+        save_slot::{data_dir, format_timestamp},
+    },
+};
+
+/// Cause of death, depth, turns and kills
+pub fn summary(world: &World) -> String {
+    let player = world.read_storage::<Player>();
+    let living = world.read_storage::<Living>();
+    let (player, living) = match (&player, &living).join().next() {
+        Some(joined) => joined,
+        None => return String::new(),
+    };
+    let cause = living
+        .last_damage
+        .as_ref()
+        .map_or("something unknown", |damage| damage.cause.as_str());
+    format!(
+        "You died on depth {} after {} turns, killed by {}.\nYou slew {} monsters.\n",
+        world.read_resource::<Map>().depth,
+        player.turns,
+        cause,
+        player.kills
+    )
+}
+
+fn inventory(world: &World) -> Vec<String> {
+    let inventory = world.read_storage::<Inventory>();
+    let player = world.read_storage::<Player>();
+    let item = world.read_storage::<Item>();
+    let name = world.read_storage::<Name>();
+    let identification = world.read_resource::<Identification>();
+    let inventory = match (&inventory, &player).join().next() {
+        Some((inventory, _)) => inventory,
+        None => return vec![],
+    };
+    inventory
+        .stacks
+        .iter()
+        .map(|stack| {
+            let top = stack.top();
+            let item_name = identification.name_of(item.get(top), name.get(top).unwrap());
+            if stack.count() > 1 {
+                format!("{} (x{})", item_name, stack.count())
+            } else {
+                item_name
+            }
+        })
+        .collect()
+}
+
+/// Write the morgue file into `morgue/` in the data directory, and say where it went
+pub fn write(world: &World) -> io::Result<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut text = format!("Tombs of the Ancient Kings, {}\n\n", format_timestamp(now));
+    text += &summary(world);
+
+    text += "\nInventory:\n";
+    let items = inventory(world);
+    if items.is_empty() {
+        text += "  nothing\n";
+    }
+    for item in items {
+        text += &format!("  {}\n", item);
+    }
+
+    text += "\nLast messages:\n";
+    for (message, _) in &world.read_resource::<Messages>().inner {
+        text += &format!("  {}\n", message);
+    }
+
+    text += "\nThe level:\n";
+    text += &to_ascii(&world.read_resource::<Map>(), &objects(world));
+
+    let dir = data_dir().join("morgue");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.txt", now));
+    fs::write(&path, text)?;
+    Ok(path)
+}
@@ -0,0 +1,46 @@
+use crate::{
+    components::Position,
+    resources::{
+        map::Map,
+        ui::{VIEW_HEIGHT, VIEW_WIDTH},
+    },
+};
+
+/// The part of the map that's on screen. Maps can be bigger than the screen,
+/// so this follows the player around.
+#[derive(Debug, Clone, Default)]
+pub struct Camera {
+    /// World position of the top left corner of the view
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Camera {
+    /// Center the view on `target`, but don't scroll past the edges of the map
+    pub fn follow(&mut self, target: &Position, map: &Map) {
+        let clamp = |value: i32, max: i32| value.min(max).max(0);
+        self.x = clamp(target.x - VIEW_WIDTH / 2, map.width - VIEW_WIDTH);
+        self.y = clamp(target.y - VIEW_HEIGHT / 2, map.height - VIEW_HEIGHT);
+    }
+
+    /// The world position under a point on the map console, `None` if it's outside of the view
+    pub fn to_world(&self, screen_x: i32, screen_y: i32) -> Option<Position> {
+        if screen_x < 0 || screen_y < 0 || screen_x >= VIEW_WIDTH || screen_y >= VIEW_HEIGHT {
+            return None;
+        }
+        Some(Position {
+            x: screen_x + self.x,
+            y: screen_y + self.y,
+        })
+    }
+
+    /// Where on the map console a world position ends up, if it's in view at all
+    pub fn to_screen(&self, position: &Position) -> Option<(i32, i32)> {
+        let (x, y) = (position.x - self.x, position.y - self.y);
+        if x >= 0 && y >= 0 && x < VIEW_WIDTH && y < VIEW_HEIGHT {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
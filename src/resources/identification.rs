@@ -0,0 +1,127 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use specs::{Component, HashMapStorage};
+use specs_derive::Component;
+use tcod::{colors, Color};
+
+use crate::components::{Item, Name};
+
+const SCROLL_LABELS: &[&str] = &[
+    "ZELGO MER",
+    "JUYED AWK YACC",
+    "NR 9",
+    "XIXAXA XOXAXA XUXAXA",
+    "PRATYAVAYAH",
+    "DAIYEN FOOELS",
+    "LEP GEX VEN ZEA",
+    "PRIRUTSENIE",
+    "ELBIB YLOH",
+    "VERR YED HORRE",
+];
+
+const SCROLL_COLORS: &[Color] = &[
+    colors::LIGHT_YELLOW,
+    colors::LIGHT_BLUE,
+    colors::DARK_RED,
+    colors::LIGHT_GREEN,
+    colors::LIGHT_SEPIA,
+    colors::LIGHT_CYAN,
+];
+
+const POTION_COLORS: &[(&str, Color)] = &[
+    ("violet", colors::VIOLET),
+    ("crimson", colors::CRIMSON),
+    ("murky", colors::DARK_SEPIA),
+    ("golden", colors::GOLD),
+    ("bubbly", colors::LIGHT_AZURE),
+    ("smoky", colors::GREY),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Appearance {
+    pub name: String,
+    pub color: Color,
+}
+
+/// What the player knows about magic items.
+/// Potions and scrolls look different in every game, and only show their true name once
+/// identified.
+#[derive(Clone, Debug, Serialize, Deserialize, Component)]
+#[storage(HashMapStorage)]
+pub struct Identification {
+    appearances: HashMap<Item, Appearance>,
+    known: HashSet<Item>,
+}
+
+impl Identification {
+    pub fn new_random() -> Identification {
+        let mut rng = rand::thread_rng();
+
+        let mut labels = SCROLL_LABELS.to_vec();
+        labels.shuffle(&mut rng);
+        let mut scroll_colors = SCROLL_COLORS.to_vec();
+        scroll_colors.shuffle(&mut rng);
+        let mut potions = POTION_COLORS.to_vec();
+        potions.shuffle(&mut rng);
+
+        let mut appearances = HashMap::new();
+        let scrolls = [Item::Lightning, Item::Confuse, Item::Fireball];
+        for (i, item) in scrolls.iter().enumerate() {
+            appearances.insert(
+                item.clone(),
+                Appearance {
+                    name: format!("scroll labeled {}", labels[i]),
+                    color: scroll_colors[i % scroll_colors.len()],
+                },
+            );
+        }
+        for (i, item) in [Item::Heal].iter().enumerate() {
+            let (adjective, color) = potions[i];
+            appearances.insert(
+                item.clone(),
+                Appearance {
+                    name: format!("{} potion", adjective),
+                    color,
+                },
+            );
+        }
+
+        Identification {
+            appearances,
+            known: HashSet::new(),
+        }
+    }
+
+    pub fn is_known(&self, item: &Item) -> bool {
+        !self.appearances.contains_key(item) || self.known.contains(item)
+    }
+
+    /// Returns true if this is the first time the item is identified
+    pub fn identify(&mut self, item: &Item) -> bool {
+        !self.is_known(item) && self.known.insert(item.clone())
+    }
+
+    /// The name the player should see for an entity
+    pub fn name_of(&self, item: Option<&Item>, name: &Name) -> String {
+        match item {
+            Some(item) if !self.is_known(item) => self.appearances[item].name.clone(),
+            _ => name.0.clone(),
+        }
+    }
+
+    /// The color items of this kind should be drawn with, if randomized
+    pub fn color_of(&self, item: &Item) -> Option<Color> {
+        self.appearances.get(item).map(|a| a.color)
+    }
+}
+
+impl Default for Identification {
+    fn default() -> Self {
+        Identification {
+            appearances: HashMap::new(),
+            known: HashSet::new(),
+        }
+    }
+}
@@ -1,4 +1,8 @@
-#[derive(Clone, Copy, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::resources::keymap::KeyBinding;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InputAction {
     Noop,
 
@@ -10,17 +14,35 @@ pub enum InputAction {
     MoveDown,
 
     PickUp,
-    Drop(usize),
+    CloseDoor,
+    Search,
+    Disarm,
+    ExportMap,
+    Drop(Vec<usize>),
     UseFromInventory(usize),
+    Fire,
+    FireAt(i32, i32),
+    ThrowAt(usize, i32, i32),
 
     OpenDropMenu,
+    OpenThrowMenu,
     OpenInventoryMenu,
+    OpenSaveMenu,
+    OpenKeymapMenu,
+    ShowHelp,
     MenuChoice(usize),
     DismissMenu,
 
+    // Key bindings, by their index in the keymap
+    BindKey(usize, KeyBinding),
+    UnbindKeys(usize),
+    ResetKeymap,
+
     // Game operations
-    NewGame,
-    LoadGame,
+    /// Start a new game with a character of this name, saving to this slot
+    NewGame(String, usize),
+    SaveGame(usize),
+    LoadGame(usize),
     MainMenu,
     Exit,
     ToggleFullScreen,
@@ -0,0 +1,463 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use tcod::input::{Key, KeyCode};
+
+use crate::resources::{input_action::InputAction, save_slot::config_dir};
+
+/// Keys that have a name, rather than typing a character
+const NAMED_KEYS: &[KeyCode] = &[
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Spacebar,
+    KeyCode::Pause,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::End,
+    KeyCode::Home,
+    KeyCode::Up,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::NumPad0,
+    KeyCode::NumPad1,
+    KeyCode::NumPad2,
+    KeyCode::NumPad3,
+    KeyCode::NumPad4,
+    KeyCode::NumPad5,
+    KeyCode::NumPad6,
+    KeyCode::NumPad7,
+    KeyCode::NumPad8,
+    KeyCode::NumPad9,
+    KeyCode::NumPadAdd,
+    KeyCode::NumPadSubtract,
+    KeyCode::NumPadDivide,
+    KeyCode::NumPadMultiply,
+    KeyCode::NumPadDecimal,
+    KeyCode::NumPadEnter,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundKey {
+    Code(KeyCode),
+    /// A typed character, whichever key it is on for the keyboard layout in use
+    Char(char),
+}
+
+/// A key and the modifiers held with it. Written like `Ctrl+s`, `Alt+Enter` or `NumPad8`.
+/// Shift only counts for named keys, for characters it's part of the character already.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBinding {
+    pub key: BoundKey,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    fn new(key: BoundKey) -> KeyBinding {
+        KeyBinding {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    /// What a key press is bound as. `None` for presses that only hold a modifier, and for the
+    /// plain letter presses that are followed by the text they type.
+    pub fn from_key(key: &Key) -> Option<KeyBinding> {
+        let (bound, shift) = match key.code {
+            KeyCode::Text => match key.text().chars().next() {
+                // the space bar has its own key code as well
+                Some(' ') | None => return None,
+                Some(c) => (BoundKey::Char(c), false),
+            },
+            // with ctrl or alt held no text is typed, so this is all we get
+            KeyCode::Char if key.ctrl || key.alt => (BoundKey::Char(key.printable), false),
+            KeyCode::Char | KeyCode::Shift | KeyCode::Control | KeyCode::Alt | KeyCode::NoKey => {
+                return None
+            }
+            code => (BoundKey::Code(code), key.shift),
+        };
+        Some(KeyBinding {
+            key: bound,
+            ctrl: key.ctrl,
+            alt: key.alt,
+            shift,
+        })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        match self.key {
+            BoundKey::Code(code) => write!(f, "{:?}", code),
+            BoundKey::Char(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<KeyBinding, String> {
+        let mut rest = s;
+        let mut modifiers = (false, false, false);
+        loop {
+            if rest.starts_with("Ctrl+") {
+                modifiers.0 = true;
+                rest = &rest["Ctrl+".len()..];
+            } else if rest.starts_with("Alt+") {
+                modifiers.1 = true;
+                rest = &rest["Alt+".len()..];
+            } else if rest.starts_with("Shift+") {
+                modifiers.2 = true;
+                rest = &rest["Shift+".len()..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) => BoundKey::Char(c),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|code| format!("{:?}", code) == rest)
+                .map(|code| BoundKey::Code(*code))
+                .ok_or_else(|| format!("unknown key `{}`", s))?,
+        };
+        Ok(KeyBinding {
+            ctrl: modifiers.0,
+            alt: modifiers.1,
+            shift: modifiers.2,
+            ..KeyBinding::new(key)
+        })
+    }
+}
+
+impl Serialize for KeyBinding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeyBinding, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Which keys do what while playing. Every action that can be bound has an entry, in the order
+/// the key bindings screen lists them.
+pub struct Keymap {
+    pub bindings: Vec<(InputAction, Vec<KeyBinding>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use self::BoundKey::*;
+        use crate::resources::input_action::InputAction::*;
+
+        let keys = |keys: &[BoundKey]| -> Vec<KeyBinding> {
+            keys.iter().cloned().map(KeyBinding::new).collect()
+        };
+        Keymap {
+            bindings: vec![
+                (
+                    MoveNorth,
+                    keys(&[Code(KeyCode::Up), Code(KeyCode::NumPad8), Char('k')]),
+                ),
+                (
+                    MoveEast,
+                    keys(&[Code(KeyCode::Right), Code(KeyCode::NumPad6), Char('l')]),
+                ),
+                (
+                    MoveSouth,
+                    keys(&[Code(KeyCode::Down), Code(KeyCode::NumPad2), Char('j')]),
+                ),
+                (
+                    MoveWest,
+                    keys(&[Code(KeyCode::Left), Code(KeyCode::NumPad4), Char('h')]),
+                ),
+                (MoveDown, keys(&[Char('>')])),
+                (PickUp, keys(&[Char('g')])),
+                (OpenInventoryMenu, keys(&[Char('i')])),
+                (OpenDropMenu, keys(&[Char('d')])),
+                (OpenThrowMenu, keys(&[Char('t')])),
+                (Fire, keys(&[Char('f')])),
+                (CloseDoor, keys(&[Char('c')])),
+                (Search, keys(&[Char('s')])),
+                (Disarm, keys(&[Char('x')])),
+                (OpenSaveMenu, keys(&[Code(KeyCode::Escape)])),
+                (OpenKeymapMenu, keys(&[Char('K')])),
+                (ShowHelp, keys(&[Char('?'), Code(KeyCode::F1)])),
+                (ExportMap, keys(&[Code(KeyCode::F12)])),
+                (
+                    ToggleFullScreen,
+                    vec![KeyBinding {
+                        alt: true,
+                        ..KeyBinding::new(Code(KeyCode::Enter))
+                    }],
+                ),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    /// `$XDG_CONFIG_HOME/rl/keymap.ron`
+    pub fn path() -> PathBuf {
+        config_dir().join("keymap.ron")
+    }
+
+    /// The keymap from the keymap file. Actions the file doesn't mention keep their default keys.
+    pub fn load() -> io::Result<Keymap> {
+        match fs::read_to_string(Keymap::path()) {
+            Ok(text) => Keymap::from_ron(&text),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Keymap::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The keymap from the contents of a keymap file
+    fn from_ron(text: &str) -> io::Result<Keymap> {
+        let mut keymap = Keymap::default();
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let bindings: Vec<(InputAction, Vec<KeyBinding>)> =
+            ron::de::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        for (action, keys) in bindings {
+            match keymap.bindings.iter_mut().find(|(a, _)| *a == action) {
+                Some(binding) => binding.1 = keys,
+                None => return Err(invalid(format!("{:?} can't be bound to a key", action))),
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(&self.bindings, Default::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        fs::create_dir_all(config_dir())?;
+        fs::write(Keymap::path(), text)
+    }
+
+    pub fn keys_for(&self, action: &InputAction) -> &[KeyBinding] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| a == action)
+            .map_or(&[][..], |(_, keys)| &keys[..])
+    }
+
+    pub fn action_for(&self, key: &KeyBinding) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(key))
+            .map(|(action, _)| action.clone())
+    }
+
+    /// Bind a key to the action at `index`, taking it away from whatever it did before
+    pub fn bind(&mut self, index: usize, key: KeyBinding) {
+        for (_, keys) in &mut self.bindings {
+            keys.retain(|k| *k != key);
+        }
+        if let Some((_, keys)) = self.bindings.get_mut(index) {
+            keys.push(key);
+        }
+    }
+
+    pub fn unbind(&mut self, index: usize) {
+        if let Some((_, keys)) = self.bindings.get_mut(index) {
+            keys.clear();
+        }
+    }
+
+    /// The keys for an action, like `Up, NumPad8, k`
+    pub fn describe_keys(keys: &[KeyBinding]) -> String {
+        if keys.is_empty() {
+            return "(none)".to_string();
+        }
+        keys.iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Why the keymap file couldn't be read. Stored as an `Option<KeymapError>` resource, so the main
+/// menu can tell the player the default keys are in use.
+#[derive(Debug)]
+pub struct KeymapError(pub io::Error);
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Could not read the key bindings from {}, using the default keys: {}",
+            Keymap::path().display(),
+            self.0
+        )
+    }
+}
+
+/// What an action is called on the key bindings screen
+pub fn describe_action(action: &InputAction) -> &'static str {
+    use crate::resources::input_action::InputAction::*;
+    match action {
+        MoveNorth => "Move north",
+        MoveEast => "Move east",
+        MoveSouth => "Move south",
+        MoveWest => "Move west",
+        MoveDown => "Take the stairs down",
+        PickUp => "Pick up an item",
+        OpenInventoryMenu => "Open the inventory",
+        OpenDropMenu => "Drop items",
+        OpenThrowMenu => "Throw an item",
+        Fire => "Fire a launcher",
+        CloseDoor => "Close a door",
+        Search => "Search for traps",
+        Disarm => "Disarm a trap",
+        OpenSaveMenu => "Save and quit",
+        OpenKeymapMenu => "Change key bindings",
+        ShowHelp => "Show this help",
+        ExportMap => "Export the map",
+        ToggleFullScreen => "Toggle full screen",
+        _ => "",
+    }
+}
+
+/// The headings the help screen groups actions under, in order
+pub const CATEGORIES: &[&str] = &["Moving", "Items", "Exploring", "Game"];
+
+/// Which of the `CATEGORIES` an action goes under
+pub fn action_category(action: &InputAction) -> &'static str {
+    use crate::resources::input_action::InputAction::*;
+    match action {
+        MoveNorth | MoveEast | MoveSouth | MoveWest | MoveDown => "Moving",
+        PickUp | OpenInventoryMenu | OpenDropMenu | OpenThrowMenu | Fire => "Items",
+        CloseDoor | Search | Disarm => "Exploring",
+        _ => "Game",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::input_action::InputAction::*;
+
+    fn parse(s: &str) -> KeyBinding {
+        s.parse().unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    #[test]
+    fn parse_modifiers() {
+        assert_eq!(
+            parse("Ctrl+s"),
+            KeyBinding {
+                ctrl: true,
+                ..KeyBinding::new(BoundKey::Char('s'))
+            }
+        );
+        assert_eq!(
+            parse("Alt+Enter"),
+            KeyBinding {
+                alt: true,
+                ..KeyBinding::new(BoundKey::Code(KeyCode::Enter))
+            }
+        );
+        assert_eq!(
+            parse("Shift+Up"),
+            KeyBinding {
+                shift: true,
+                ..KeyBinding::new(BoundKey::Code(KeyCode::Up))
+            }
+        );
+        assert_eq!(
+            parse("NumPad8"),
+            KeyBinding::new(BoundKey::Code(KeyCode::NumPad8))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for s in &[
+            "Ctrl+s",
+            "Alt+Enter",
+            "Shift+Up",
+            "NumPad8",
+            "Ctrl+Alt+Shift+F12",
+            "k",
+            "K",
+            "?",
+            "+",
+        ] {
+            assert_eq!(parse(s).to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn default_keys_round_trip() {
+        for (_, keys) in Keymap::default().bindings {
+            for key in keys {
+                assert_eq!(parse(&key.to_string()), key);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        for s in &["", "Ctrl+", "NumPad10", "Super+s", "sk", "Text", "up"] {
+            assert!(s.parse::<KeyBinding>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn partial_keymap_keeps_other_defaults() {
+        let keymap = Keymap::from_ron(r#"[(MoveNorth, ["w"]), (Search, [])]"#).unwrap();
+        assert_eq!(keymap.keys_for(&MoveNorth), &[parse("w")][..]);
+        assert!(keymap.keys_for(&Search).is_empty());
+        let defaults = Keymap::default();
+        assert_eq!(keymap.keys_for(&MoveSouth), defaults.keys_for(&MoveSouth));
+        assert_eq!(
+            keymap.keys_for(&ToggleFullScreen),
+            defaults.keys_for(&ToggleFullScreen)
+        );
+    }
+
+    #[test]
+    fn unknown_key_in_keymap_file_is_an_error() {
+        assert!(Keymap::from_ron(r#"[(MoveNorth, ["Hyper+w"])]"#).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 
 use serde::{Deserialize, Serialize};
 use specs::{join::JoinIter, Component, HashMapStorage, ReadStorage, World};
@@ -6,30 +6,115 @@ use specs_derive::Component;
 
 use crate::components::{Collider, Position};
 
+/// Size of a regular level, some levels are bigger. See `Map::width` and `Map::height`.
 pub const MAP_WIDTH: i32 = 80;
 pub const MAP_HEIGHT: i32 = 43;
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Door {
+    pub open: bool,
+    pub locked: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TileKind {
+    Floor,
+    Wall,
+    Door(Door),
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Chasm,
+    Rubble,
+}
+
+impl TileKind {
+    /// Energy multiplier for stepping onto a tile of this kind
+    pub fn movement_cost(&self) -> u8 {
+        match self {
+            TileKind::ShallowWater | TileKind::Rubble => 2,
+            TileKind::DeepWater => 3,
+            _ => 1,
+        }
+    }
+
+    /// Tiles that hurt (or worse) anything that steps on them. Monsters know better.
+    pub fn is_hazardous(&self) -> bool {
+        match self {
+            TileKind::DeepWater | TileKind::Lava | TileKind::Chasm => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tile {
-    pub blocked: bool,
-    pub block_sight: bool,
+    pub kind: TileKind,
     pub explored: bool,
 }
 
 impl Tile {
-    pub fn floor() -> Self {
+    pub fn new(kind: TileKind) -> Self {
         Tile {
-            blocked: false,
-            block_sight: false,
+            kind,
             explored: false,
         }
     }
 
+    pub fn floor() -> Self {
+        Tile::new(TileKind::Floor)
+    }
+
     pub fn wall() -> Self {
-        Tile {
-            blocked: true,
-            block_sight: true,
-            explored: false,
+        Tile::new(TileKind::Wall)
+    }
+
+    /// A closed door, optionally locked
+    pub fn door(locked: bool) -> Self {
+        Tile::new(TileKind::Door(Door {
+            open: false,
+            locked,
+        }))
+    }
+
+    pub fn blocked(&self) -> bool {
+        match &self.kind {
+            TileKind::Wall => true,
+            TileKind::Door(door) => !door.open,
+            _ => false,
+        }
+    }
+
+    pub fn block_sight(&self) -> bool {
+        self.blocked()
+    }
+
+    pub fn as_door(&self) -> Option<&Door> {
+        match &self.kind {
+            TileKind::Door(door) => Some(door),
+            _ => None,
+        }
+    }
+
+    pub fn is_closed_door(&self) -> bool {
+        self.as_door().map_or(false, |door| !door.open)
+    }
+
+    pub fn is_open_door(&self) -> bool {
+        self.as_door().map_or(false, |door| door.open)
+    }
+
+    /// Opening a door also unlocks it
+    pub fn open_door(&mut self) {
+        if let TileKind::Door(door) = &mut self.kind {
+            door.open = true;
+            door.locked = false;
+        }
+    }
+
+    pub fn close_door(&mut self) {
+        if let TileKind::Door(door) = &mut self.kind {
+            door.open = false;
         }
     }
 }
@@ -40,36 +125,30 @@ pub type Tiles = Vec<Vec<Tile>>;
 #[storage(HashMapStorage)]
 pub struct Map {
     pub tiles: Tiles,
+    pub width: i32,
+    pub height: i32,
     pub spawn_point: Position,
+    /// How many levels down this is, starting at 1
+    pub depth: u32,
 }
 
 impl Map {
     pub fn empty() -> Map {
         Map {
             tiles: vec![vec![]],
+            width: 0,
+            height: 0,
             spawn_point: Position { x: 0, y: 0 },
+            depth: 0,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn new_simple() -> Map {
-        use crate::mapgen::*;
-        // fill map with "unblocked" tiles
-        let mut tiles = vec![vec![Tile::wall(); MAP_HEIGHT as usize]; MAP_WIDTH as usize];
-
-        // Carve out a couple of rooms
-        create_room(Rect::new(20, 15, 10, 15), &mut tiles);
-        create_room(Rect::new(50, 15, 10, 15), &mut tiles);
-        create_h_tunnel(25, 55, 23, &mut tiles);
-
-        Map {
-            tiles,
-            spawn_point: Position { x: 25, y: 23 },
-        }
+    pub fn in_bounds(&self, position: &Position) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.width && position.y < self.height
     }
 
-    pub fn new_random(world: &mut World) {
-        crate::mapgen::generate_map(world)
+    pub fn new_random(world: &mut World, depth: u32) {
+        crate::mapgen::generate_map(world, depth)
     }
 }
 
@@ -86,7 +165,7 @@ impl<'a> CalculateBlockedMapExt<JoinIter<(&ReadStorage<'a, Position>, &ReadStora
         join: JoinIter<(&ReadStorage<Position>, &ReadStorage<Collider>)>,
     ) -> bool {
         // First check for walls
-        if self[pos].blocked {
+        if self[pos].blocked() {
             return true;
         }
         // Check for objects blocking movement
@@ -130,3 +209,9 @@ impl Index<&Position> for Map {
         &self.tiles[position.x as usize][position.y as usize]
     }
 }
+
+impl IndexMut<&Position> for Map {
+    fn index_mut(&mut self, position: &Position) -> &mut Tile {
+        &mut self.tiles[position.x as usize][position.y as usize]
+    }
+}
@@ -1,14 +1,240 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::resources::{input_action::InputAction, keymap::KeyBinding, targeting::Targeting};
+
+/// Entries are labelled a-z, so that's how many fit on a page
+pub const MENU_PAGE_SIZE: usize = 26;
+
 #[derive(Clone, PartialEq)]
+pub struct MenuItem {
+    pub label: String,
+    /// Disabled entries are greyed out, and can't be picked
+    pub enabled: bool,
+    /// Ticked in a multi-select menu
+    pub selected: bool,
+}
+
+impl MenuItem {
+    pub fn new<T: Into<String>>(label: T) -> MenuItem {
+        MenuItem {
+            label: label.into(),
+            enabled: true,
+            selected: false,
+        }
+    }
+
+    pub fn disabled<T: Into<String>>(label: T) -> MenuItem {
+        MenuItem {
+            enabled: false,
+            ..MenuItem::new(label)
+        }
+    }
+}
+
+/// What the player picked in a menu
+pub struct MenuSelection {
+    /// The entry that was picked
+    pub index: usize,
+    /// The ticked entries, in a multi-select menu
+    pub selected: Vec<usize>,
+    /// What was typed, in a text entry dialog
+    pub text: String,
+    /// The key that was pressed, in a key capture dialog
+    pub key: Option<KeyBinding>,
+}
+
+/// What picking an entry, or backing out of a menu, leads to
+pub enum MenuResult {
+    /// Close all menus and do this
+    Action(InputAction),
+    /// Open another menu on top of this one, backing out of it comes back here
+    Open(Menu),
+    /// Show another menu instead of this one
+    Replace(Menu),
+    /// Go back to the menu this one was opened from, or close it if there is none
+    Back,
+    /// Close all menus and have the player pick a target, telling them how
+    Target(Targeting, &'static str),
+}
+
+pub type MenuCallback = Arc<dyn Fn(&MenuSelection) -> MenuResult + Send + Sync>;
+
 pub struct Menu {
-    pub items: Vec<String>,
+    pub items: Vec<MenuItem>,
     pub header: String,
     pub width: i32,
-    pub kind: MenuKind,
+    /// The highlighted entry, which Enter picks. The page shown is the one it's on.
+    pub cursor: usize,
+    /// Where the first entry of the page was last drawn, to tell what the mouse is over
+    pub top_left: Option<(i32, i32)>,
+    /// The entry the mouse was over last time we looked
+    pub hovered: Option<usize>,
+    /// Letters and space tick entries instead of picking them, Enter picks them all
+    pub multi_select: bool,
+    /// What's been typed so far, if this is a text entry dialog
+    pub text: Option<String>,
+    /// Any key but escape is taken as the choice, to bind it to something
+    pub capture_key: bool,
+    pub captured_key: Option<KeyBinding>,
+    /// The menu to go back to
+    pub parent: Option<Box<Menu>>,
+    on_choose: MenuCallback,
+    on_dismiss: MenuCallback,
+}
+
+impl Menu {
+    pub fn new<F>(header: String, width: i32, items: Vec<MenuItem>, on_choose: F) -> Menu
+    where
+        F: Fn(&MenuSelection) -> MenuResult + Send + Sync + 'static,
+    {
+        let cursor = items.iter().position(|item| item.enabled).unwrap_or(0);
+        Menu {
+            items,
+            header,
+            width,
+            cursor,
+            top_left: None,
+            hovered: None,
+            multi_select: false,
+            text: None,
+            capture_key: false,
+            captured_key: None,
+            parent: None,
+            on_choose: Arc::new(on_choose),
+            on_dismiss: Arc::new(|_| MenuResult::Back),
+        }
+    }
+
+    /// A dialog asking for a line of text, which Enter confirms
+    pub fn text_entry<F>(header: String, width: i32, text: &str, on_choose: F) -> Menu
+    where
+        F: Fn(&MenuSelection) -> MenuResult + Send + Sync + 'static,
+    {
+        Menu {
+            text: Some(text.to_string()),
+            ..Menu::new(header, width, vec![], on_choose)
+        }
+    }
+
+    /// A dialog waiting for a key press
+    pub fn key_capture<F>(header: String, width: i32, on_choose: F) -> Menu
+    where
+        F: Fn(&MenuSelection) -> MenuResult + Send + Sync + 'static,
+    {
+        Menu {
+            capture_key: true,
+            ..Menu::new(header, width, vec![], on_choose)
+        }
+    }
+
+    pub fn multi_select(self) -> Menu {
+        Menu {
+            multi_select: true,
+            ..self
+        }
+    }
+
+    /// What happens on escape, instead of going back
+    pub fn on_dismiss<F>(self, on_dismiss: F) -> Menu
+    where
+        F: Fn(&MenuSelection) -> MenuResult + Send + Sync + 'static,
+    {
+        Menu {
+            on_dismiss: Arc::new(on_dismiss),
+            ..self
+        }
+    }
+
+    pub fn choose(&self) -> MenuResult {
+        (self.on_choose)(&self.selection())
+    }
+
+    pub fn dismiss(&self) -> MenuResult {
+        (self.on_dismiss)(&self.selection())
+    }
+
+    fn selection(&self) -> MenuSelection {
+        MenuSelection {
+            index: self.cursor,
+            selected: (0..self.items.len())
+                .filter(|index| self.items[*index].selected)
+                .collect(),
+            text: self.text.clone().unwrap_or_default(),
+            key: self.captured_key,
+        }
+    }
+
+    /// Whether Enter does anything right now
+    pub fn can_choose(&self) -> bool {
+        if self.text.is_some() {
+            true
+        } else if self.capture_key {
+            self.captured_key.is_some()
+        } else if self.multi_select {
+            self.items.iter().any(|item| item.selected)
+        } else {
+            self.is_enabled(self.cursor)
+        }
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if self.is_enabled(index) {
+            let item = &mut self.items[index];
+            item.selected = !item.selected;
+        }
+    }
+
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.items.get(index).map_or(false, |item| item.enabled)
+    }
+
+    pub fn num_pages(&self) -> usize {
+        ((self.items.len() + MENU_PAGE_SIZE - 1) / MENU_PAGE_SIZE).max(1)
+    }
+
+    pub fn page(&self) -> usize {
+        self.cursor / MENU_PAGE_SIZE
+    }
+
+    /// Indices of the entries on the current page
+    pub fn page_range(&self) -> Range<usize> {
+        let start = self.page() * MENU_PAGE_SIZE;
+        start..(start + MENU_PAGE_SIZE).min(self.items.len())
+    }
+
+    /// Move the cursor `by` entries up or down, to the nearest entry that can be picked
+    pub fn move_cursor(&mut self, by: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() as isize - 1;
+        let target = (self.cursor as isize + by).max(0).min(last);
+        let step = if by < 0 { -1 } else { 1 };
+        let mut ahead = (0..)
+            .map(|n| target + n * step)
+            .take_while(|index| *index >= 0 && *index <= last);
+        let mut behind = (1..)
+            .map(|n| target - n * step)
+            .take_while(|index| *index >= 0 && *index <= last);
+        if let Some(index) = ahead
+            .find(|index| self.is_enabled(*index as usize))
+            .or_else(|| behind.find(|index| self.is_enabled(*index as usize)))
+        {
+            self.cursor = index as usize;
+        }
+    }
+}
+
+/// The letter for an entry, by its position on the page
+pub fn menu_letter(index: usize) -> char {
+    (b'a' + index as u8) as char
 }
 
-#[derive(Copy, Clone, PartialEq)]
-pub enum MenuKind {
-    Inventory,
-    Drop,
-    Main,
+/// The position on the page of the entry with this letter
+pub fn menu_index(letter: char) -> Option<usize> {
+    match letter {
+        'a'..='z' => Some(letter as usize - 'a' as usize),
+        _ => None,
+    }
 }
@@ -1,7 +1,12 @@
+pub mod camera;
+pub mod identification;
 pub mod input_action;
+pub mod keymap;
 pub mod map;
 pub mod menu;
 pub mod messages;
+pub mod save_error;
+pub mod save_slot;
 pub mod state;
 pub mod targeting;
 pub mod ui;
@@ -0,0 +1,39 @@
+use std::fmt;
+use std::io;
+
+/// Why saving or loading didn't work out. Stored as an `Option<SaveError>` resource by the save
+/// and load systems, so the UI can tell the player.
+#[derive(Debug)]
+pub enum SaveError {
+    /// There's no saved game to load
+    NoSave,
+    Io(io::Error),
+    /// The game state couldn't be written
+    Serialize(String),
+    /// There is a save file, but it doesn't hold a game we can load
+    Corrupt(String),
+    /// The save file is fine, but from a version of the game we can't load
+    Incompatible(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::NoSave => write!(f, "No saved game found."),
+            SaveError::Io(e) => write!(f, "Could not access the saved game: {}", e),
+            SaveError::Serialize(e) => write!(f, "Could not save the game: {}", e),
+            SaveError::Corrupt(e) => write!(f, "The saved game is corrupt: {}", e),
+            SaveError::Incompatible(e) => write!(f, "The saved game can't be loaded: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::NotFound {
+            SaveError::NoSave
+        } else {
+            SaveError::Io(e)
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::resources::save_error::SaveError;
+use crate::save_format::SaveHeader;
+
+pub const NUM_SAVE_SLOTS: usize = 5;
+
+/// Which save slot the save and load systems work with, counting from 0.
+/// This is also where the current game autosaves.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SaveSlot(pub usize);
+
+/// Keep the save around when the player dies, instead of deleting it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExplorerMode(pub bool);
+
+impl SaveSlot {
+    /// Where a new game goes: the first empty slot. `None` if they're all taken,
+    /// the player has to choose a game to replace then.
+    pub fn for_new_game() -> Option<SaveSlot> {
+        (0..NUM_SAVE_SLOTS)
+            .map(SaveSlot)
+            .find(|slot| slot.is_empty())
+    }
+
+    pub fn path(self) -> PathBuf {
+        saves_dir().join(format!("slot{}.sav", self.0 + 1))
+    }
+
+    pub fn is_empty(self) -> bool {
+        !self.path().exists()
+    }
+
+    pub fn delete(self) -> Result<(), SaveError> {
+        fs::remove_file(self.path())?;
+        Ok(())
+    }
+
+    /// What's in the slot, in one line for the save and load menus
+    pub fn describe(self) -> String {
+        let summary = match SaveHeader::read(&self.path()) {
+            Ok(header) => format!(
+                "{}, depth {}, turn {}, {}",
+                header.name,
+                header.depth,
+                header.turns,
+                format_timestamp(header.saved_at)
+            ),
+            Err(SaveError::NoSave) => "empty".to_string(),
+            Err(_) => "unreadable".to_string(),
+        };
+        format!("Slot {}: {}", self.0 + 1, summary)
+    }
+}
+
+/// Our directory under an XDG base directory, or under its default in the home directory
+fn xdg_dir(variable: &str, default: &str) -> PathBuf {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(default)))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join(env!("CARGO_PKG_NAME"))
+}
+
+/// `$XDG_DATA_HOME/rl`, which is usually `~/.local/share/rl`
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$XDG_CONFIG_HOME/rl`, which is usually `~/.config/rl`
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn saves_dir() -> PathBuf {
+    data_dir().join("saves")
+}
+
+/// Seconds since the epoch as a UTC date and time, like `2019-03-14 15:09`
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let (hour, minute) = (secs % 86_400 / 3600, secs % 3600 / 60);
+
+    // Convert days to a date in the proleptic Gregorian calendar, in eras of 400 years
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+    }
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!(format_timestamp(1_552_576_140), "2019-03-14 15:09");
+        assert_eq!(format_timestamp(1_552_607_999), "2019-03-14 23:59");
+    }
+
+    #[test]
+    fn leap_days() {
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(951_868_800), "2000-03-01 00:00");
+        assert_eq!(format_timestamp(1_582_934_400), "2020-02-29 00:00");
+        assert_eq!(format_timestamp(1_609_372_800), "2020-12-31 00:00");
+    }
+
+    #[test]
+    fn century_without_leap_day() {
+        // 2100 is divisible by 4 but not by 400, so February ends on the 28th
+        assert_eq!(format_timestamp(4_107_456_000), "2100-02-28 00:00");
+        assert_eq!(format_timestamp(4_107_542_400), "2100-03-01 00:00");
+    }
+}
@@ -3,6 +3,10 @@ pub enum State {
     MainMenu,
     Game,
     Loaded,
+    /// The player left the level in the middle of a turn, and needs a new one
+    Descending,
+    /// The player died, and is looking at the death screen
+    GameOver,
 }
 
 impl Default for State {
@@ -3,6 +3,7 @@ use specs::Entity;
 pub struct Targeting {
     pub used_item: Entity,
     pub kind: TargetingKind,
+    pub intent: TargetingIntent,
     pub max_range: Option<f32>,
 }
 
@@ -11,3 +12,11 @@ pub enum TargetingKind {
     Monster,
     Tile,
 }
+
+/// What happens once a target is picked
+#[derive(PartialEq)]
+pub enum TargetingIntent {
+    UseItem,
+    Fire,
+    Throw,
+}
@@ -1,12 +1,13 @@
 use std::sync::{Arc, Mutex};
 use tcod::console::*;
 
-use crate::resources::map::{MAP_HEIGHT, MAP_WIDTH};
-
 pub const BAR_WIDTH: i32 = 20;
 pub const PANEL_HEIGHT: i32 = 7;
-pub const SCREEN_WIDTH: i32 = 80;
-pub const SCREEN_HEIGHT: i32 = MAP_HEIGHT + PANEL_HEIGHT;
+/// Size of the part of the map that's on screen at once
+pub const VIEW_WIDTH: i32 = 80;
+pub const VIEW_HEIGHT: i32 = 43;
+pub const SCREEN_WIDTH: i32 = VIEW_WIDTH;
+pub const SCREEN_HEIGHT: i32 = VIEW_HEIGHT + PANEL_HEIGHT;
 pub const LIMIT_FPS: i32 = 20;
 pub const PANEL_Y: i32 = SCREEN_HEIGHT - PANEL_HEIGHT;
 pub const INVENTORY_WIDTH: i32 = 50;
@@ -64,8 +65,8 @@ pub fn init(config: UIConfig) -> UIState {
         config,
         consoles: Arc::new(Mutex::new(UIConsoles {
             root,
-            map: Offscreen::new(MAP_WIDTH, MAP_HEIGHT),
-            panel: Offscreen::new(MAP_WIDTH, SCREEN_HEIGHT - MAP_HEIGHT),
+            map: Offscreen::new(VIEW_WIDTH, VIEW_HEIGHT),
+            panel: Offscreen::new(SCREEN_WIDTH, PANEL_HEIGHT),
         })),
     }
 }
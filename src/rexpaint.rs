@@ -0,0 +1,219 @@
+//! Reader for REXPaint `.xp` images: gzip-compressed stacks of layers,
+//! each a grid of glyphs with a foreground and a background colour.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use tcod::{colors::Color, console::Console};
+
+/// REXPaint marks transparent cells with a hot pink background
+const TRANSPARENT: Color = Color {
+    r: 255,
+    g: 0,
+    b: 255,
+};
+
+/// Far bigger than anything drawn by hand. Layers claiming more cells than this come from a broken
+/// file, and aren't worth allocating memory for.
+const MAX_LAYER_CELLS: i32 = 4_000_000;
+
+/// CP437 codes the tcod font has glyphs of its own for: arrows, shading and the single and
+/// double box drawing lines. libtcod takes these under their CP437 codes.
+const FONT_GLYPHS: &[u32] = &[
+    16, 17, 18, 24, 25, 26, 27, 29, 30, 31, 176, 177, 178, 179, 180, 185, 186, 187, 188, 191, 192,
+    193, 194, 195, 196, 197, 200, 201, 202, 203, 204, 205, 206, 217, 218, 219,
+];
+
+/// The closest ASCII characters to CP437 codes 0 to 31, and 128 to 255,
+/// for drawing the ones the font doesn't have
+const CP437_LOW_AS_ASCII: &[u8; 32] = b" @@v*%%*#o#mfdd*><|!PS-|^v><L-^v";
+const CP437_HIGH_AS_ASCII: &[u8; 128] = b"CueaaaaceeeiiiAAEaAooouuyOUcLYPfaiounNao?--%%!<>\
+###|++++++|+++++++++-++++++++=+++++++++++++#####abGpSsmtFTOd8fen=+><()/~o..vn2# ";
+
+#[derive(Debug)]
+pub enum XpError {
+    Io(io::Error),
+    Format(String),
+}
+
+impl fmt::Display for XpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XpError::Io(e) => write!(f, "Couldn't read REXPaint file: {}", e),
+            XpError::Format(e) => write!(f, "Invalid REXPaint file: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for XpError {
+    fn from(e: io::Error) -> Self {
+        XpError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct XpCell {
+    /// CP437 code
+    pub glyph: u32,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl XpCell {
+    pub fn is_transparent(&self) -> bool {
+        self.background == TRANSPARENT
+    }
+
+    /// The glyph as a character, if it's plain ASCII
+    pub fn ascii(&self) -> Option<char> {
+        if self.glyph < 128 {
+            std::char::from_u32(self.glyph)
+        } else {
+            None
+        }
+    }
+
+    /// The glyph as the character the tcod font draws for it
+    pub fn font_char(&self) -> char {
+        let code = self.glyph;
+        if (code >= 32 && code < 128) || FONT_GLYPHS.contains(&code) {
+            std::char::from_u32(code).unwrap_or(' ')
+        } else if code < 32 {
+            CP437_LOW_AS_ASCII[code as usize] as char
+        } else if code < 256 {
+            CP437_HIGH_AS_ASCII[code as usize - 128] as char
+        } else {
+            ' '
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XpLayer {
+    pub width: i32,
+    pub height: i32,
+    /// Stored column by column, like in the file
+    cells: Vec<XpCell>,
+}
+
+impl XpLayer {
+    pub fn get(&self, x: i32, y: i32) -> Option<&XpCell> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get((x * self.height + y) as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XpFile {
+    pub layers: Vec<XpLayer>,
+}
+
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    let mut buffer = [0; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(i32::from_le_bytes(buffer))
+}
+
+fn read_color(reader: &mut impl Read) -> io::Result<Color> {
+    let mut buffer = [0; 3];
+    reader.read_exact(&mut buffer)?;
+    Ok(Color {
+        r: buffer[0],
+        g: buffer[1],
+        b: buffer[2],
+    })
+}
+
+impl XpFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<XpFile, XpError> {
+        XpFile::read(File::open(path)?)
+    }
+
+    /// Read a (still compressed) `.xp` file
+    pub fn read<R: Read>(reader: R) -> Result<XpFile, XpError> {
+        let mut reader = GzDecoder::new(reader);
+        // the version is negative, older files without it start with the layer count
+        let version = read_i32(&mut reader)?;
+        let num_layers = if version < 0 {
+            read_i32(&mut reader)?
+        } else {
+            version
+        };
+        if num_layers < 1 {
+            return Err(XpError::Format(format!("{} layers", num_layers)));
+        }
+
+        let mut layers = vec![];
+        for _ in 0..num_layers {
+            let width = read_i32(&mut reader)?;
+            let height = read_i32(&mut reader)?;
+            let cell_count = match width.checked_mul(height) {
+                Some(count) if width >= 0 && height >= 0 && count <= MAX_LAYER_CELLS => count,
+                _ => return Err(XpError::Format(format!("layer size {}x{}", width, height))),
+            };
+            let mut cells = Vec::with_capacity(cell_count as usize);
+            for _ in 0..cell_count {
+                let glyph = read_i32(&mut reader)? as u32;
+                let foreground = read_color(&mut reader)?;
+                let background = read_color(&mut reader)?;
+                cells.push(XpCell {
+                    glyph,
+                    foreground,
+                    background,
+                });
+            }
+            layers.push(XpLayer {
+                width,
+                height,
+                cells,
+            });
+        }
+        Ok(XpFile { layers })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.layers[0].width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.layers[0].height
+    }
+
+    /// Draw every layer on top of each other, with the top left corner at `x`, `y`
+    pub fn draw<C: Console>(&self, console: &mut C, x: i32, y: i32) {
+        for layer in &self.layers {
+            for cx in 0..layer.width {
+                for cy in 0..layer.height {
+                    let cell = layer.get(cx, cy).unwrap();
+                    if cell.is_transparent() {
+                        continue;
+                    }
+                    console.put_char_ex(
+                        x + cx,
+                        y + cy,
+                        cell.font_char(),
+                        cell.foreground,
+                        cell.background,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Like `draw`, but for when the art is only decoration: if the file can't be read
+/// the console is left alone.
+pub fn draw_file<C: Console, P: AsRef<Path>>(console: &mut C, path: P, x: i32, y: i32) -> bool {
+    match XpFile::open(path) {
+        Ok(file) => {
+            file.draw(console, x, y);
+            true
+        }
+        Err(_) => false,
+    }
+}
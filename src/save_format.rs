@@ -0,0 +1,633 @@
+//! The layout of save files: a header saying which version of the format it is, followed by a map
+//! from component names to the components of every entity that has one. Each component's list is
+//! encoded by itself and stored as one value, so it can be skipped without knowing its layout.
+//! That's written either as compressed bincode, or as RON for poking at by hand.
+
+use std::fmt::Display;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bincode::{DefaultOptions, Options};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{
+    de::{DeserializeOwned, MapAccess},
+    ser::{Error as _, SerializeMap},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use shred_derive::SystemData;
+use specs::{
+    error::NoError,
+    prelude::*,
+    saveload::{ConvertSaveload, MarkerAllocator, U64Marker, U64MarkerAllocator},
+    storage::MaskedStorage,
+    world::EntitiesRes,
+};
+
+use crate::components::*;
+use crate::resources::{
+    identification::Identification, map::Map, messages::Messages, save_error::SaveError,
+};
+use crate::systems::save::Synthetic;
+
+/// Bump this whenever a save from the previous version can't be read as is, and add a migration
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Saves from before this version can't be read anymore
+pub const OLDEST_FORMAT_VERSION: u32 = 1;
+
+/// Rewrites the encoded entries of one component from its old layout to its new one, usually
+/// with `SaveBackend::convert_entries`
+pub type Convert = fn(SaveBackend, Vec<u8>) -> Result<Vec<u8>, String>;
+
+/// What changed in a version of the format, so older saves can be read by newer builds
+pub struct Migration {
+    /// The format version this migration brings a save up to
+    pub version: u32,
+    /// Components that got a new name, as (old, new)
+    pub renamed: &'static [(&'static str, &'static str)],
+    /// Components that are gone from the game, their data is skipped
+    pub removed: &'static [&'static str],
+    /// Components whose fields changed, by their name after renaming
+    pub converted: &'static [(&'static str, Convert)],
+}
+
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// How saves are written to disk. Loading works out which one it is by itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaveBackend {
+    /// Gzipped bincode, small and quick
+    Binary,
+    /// Plain text, for debugging
+    Ron,
+}
+
+impl Default for SaveBackend {
+    fn default() -> Self {
+        SaveBackend::Binary
+    }
+}
+
+/// Binary saves start with this, so they can be told apart from RON ones
+const BINARY_MAGIC: &[u8] = b"RLSAVE";
+
+/// No save comes anywhere near this many bytes. bincode sizes strings and lists by the lengths
+/// written in the save, so reading binary saves with this limit keeps a corrupt length from
+/// asking for gigabytes of memory.
+const MAX_SAVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// How binary saves are decoded
+fn binary_read_options() -> impl Options {
+    DefaultOptions::new().with_limit(MAX_SAVE_SIZE)
+}
+
+impl SaveBackend {
+    pub fn detect(bytes: &[u8]) -> SaveBackend {
+        if bytes.starts_with(BINARY_MAGIC) {
+            SaveBackend::Binary
+        } else {
+            SaveBackend::Ron
+        }
+    }
+
+    /// Encode a save: the header, and then whatever comes after it
+    pub fn encode<T: Serialize>(self, header: &SaveHeader, body: &T) -> Result<Vec<u8>, SaveError> {
+        match self {
+            SaveBackend::Binary => {
+                let mut encoder = GzEncoder::new(BINARY_MAGIC.to_vec(), Compression::default());
+                {
+                    let mut ser = bincode::Serializer::new(&mut encoder, DefaultOptions::new());
+                    header.serialize(&mut ser).map_err(serialize_error)?;
+                    body.serialize(&mut ser).map_err(serialize_error)?;
+                }
+                Ok(encoder.finish()?)
+            }
+            SaveBackend::Ron => {
+                let mut ser = ron::ser::Serializer::new(None, false);
+                header.serialize(&mut ser).map_err(serialize_error)?;
+                body.serialize(&mut ser).map_err(serialize_error)?;
+                Ok(ser.into_output_string().into_bytes())
+            }
+        }
+    }
+
+    /// Encode the entries of one component by themselves
+    fn encode_entries<T: Serialize>(self, entries: &T) -> Result<Vec<u8>, String> {
+        match self {
+            SaveBackend::Binary => DefaultOptions::new()
+                .serialize(entries)
+                .map_err(|e| e.to_string()),
+            SaveBackend::Ron => ron::ser::to_string(entries)
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn decode_entries<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            SaveBackend::Binary => binary_read_options()
+                .deserialize(bytes)
+                .map_err(|e| e.to_string()),
+            SaveBackend::Ron => ron::de::from_bytes(bytes).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Decode the entries of one component as `Old`, and encode them again as what `convert`
+    /// makes of each of them. `Old` is a copy of the component as it used to be saved.
+    pub fn convert_entries<Old, New>(
+        self,
+        bytes: &[u8],
+        convert: impl Fn(Old) -> New,
+    ) -> Result<Vec<u8>, String>
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+    {
+        let entries: Vec<(U64Marker, Old)> = self.decode_entries(bytes)?;
+        let entries: Vec<(U64Marker, New)> = entries
+            .into_iter()
+            .map(|(marker, old)| (marker, convert(old)))
+            .collect();
+        self.encode_entries(&entries)
+    }
+
+    /// Read the encoded entries of the next component out of the save
+    pub fn next_entries<'de, A: MapAccess<'de>>(self, map: &mut A) -> Result<Vec<u8>, A::Error> {
+        match self {
+            SaveBackend::Binary => map.next_value(),
+            SaveBackend::Ron => map.next_value::<String>().map(String::into_bytes),
+        }
+    }
+}
+
+/// The encoded entries of one component, as they're stored in the save: a length-prefixed run
+/// of bytes in binary saves, and a string of RON in RON saves so they stay readable
+struct EncodedEntries {
+    backend: SaveBackend,
+    bytes: Vec<u8>,
+}
+
+impl Serialize for EncodedEntries {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.backend {
+            SaveBackend::Binary => self.bytes.serialize(serializer),
+            SaveBackend::Ron => std::str::from_utf8(&self.bytes)
+                .map_err(S::Error::custom)?
+                .serialize(serializer),
+        }
+    }
+}
+
+fn serialize_error<E: Display>(e: E) -> SaveError {
+    SaveError::Serialize(e.to_string())
+}
+
+pub fn corrupt<E: Display>(e: E) -> SaveError {
+    SaveError::Corrupt(e.to_string())
+}
+
+/// Reads a save from whichever deserializer fits its format
+pub trait SaveReader {
+    type Output;
+
+    fn read<'de, D>(self, de: &mut D, backend: SaveBackend) -> Result<Self::Output, SaveError>
+    where
+        for<'a> &'a mut D: Deserializer<'de>;
+}
+
+pub fn read_save<R: SaveReader>(bytes: &[u8], reader: R) -> Result<R::Output, SaveError> {
+    let backend = SaveBackend::detect(bytes);
+    match backend {
+        SaveBackend::Binary => {
+            let decoder = GzDecoder::new(&bytes[BINARY_MAGIC.len()..]);
+            let mut de = bincode::Deserializer::with_reader(decoder, binary_read_options());
+            reader.read(&mut de, backend)
+        }
+        SaveBackend::Ron => {
+            let mut de = ron::de::Deserializer::from_bytes(bytes).map_err(corrupt)?;
+            reader.read(&mut de, backend)
+        }
+    }
+}
+
+struct HeaderOnly;
+
+impl SaveReader for HeaderOnly {
+    type Output = SaveHeader;
+
+    fn read<'de, D>(self, de: &mut D, _: SaveBackend) -> Result<SaveHeader, SaveError>
+    where
+        for<'a> &'a mut D: Deserializer<'de>,
+    {
+        SaveHeader::deserialize(de).map_err(corrupt)
+    }
+}
+
+/// Comes before the components. Besides the versions, it has a summary of the game for the
+/// save slot list.
+#[derive(Serialize, Deserialize)]
+pub struct SaveHeader {
+    pub format_version: u32,
+    pub game_version: String,
+    #[serde(default)]
+    pub depth: u32,
+    #[serde(default)]
+    pub turns: u32,
+    /// When the game was saved, in seconds since the epoch
+    #[serde(default)]
+    pub saved_at: u64,
+    /// The player's name
+    #[serde(default)]
+    pub name: String,
+}
+
+impl SaveHeader {
+    pub fn current(name: &str, depth: u32, turns: u32) -> Self {
+        SaveHeader {
+            format_version: FORMAT_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            depth,
+            turns,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            name: name.to_string(),
+        }
+    }
+
+    /// Read just the header of a save, to show what's in it without loading all of it
+    pub fn read(path: &Path) -> Result<SaveHeader, SaveError> {
+        read_save(&fs::read(path)?, HeaderOnly)
+    }
+
+    /// Make sure we know how to read the rest of the save
+    pub fn check(&self) -> Result<(), SaveError> {
+        if self.format_version > FORMAT_VERSION {
+            Err(SaveError::Incompatible(format!(
+                "it was saved by a newer version of the game ({})",
+                self.game_version
+            )))
+        } else if self.format_version < OLDEST_FORMAT_VERSION {
+            Err(SaveError::Incompatible(format!(
+                "it was saved by an old version of the game ({})",
+                self.game_version
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Bring the entries of a component saved under `name` in the given format version up to date,
+/// returning what the component is called now along with them. `None` if it doesn't exist
+/// anymore.
+pub fn migrate(
+    version: u32,
+    name: String,
+    bytes: Vec<u8>,
+    backend: SaveBackend,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    migrate_with(MIGRATIONS, version, name, bytes, backend)
+}
+
+fn migrate_with(
+    migrations: &[Migration],
+    version: u32,
+    mut name: String,
+    mut bytes: Vec<u8>,
+    backend: SaveBackend,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        if migration.removed.contains(&name.as_str()) {
+            return Ok(None);
+        }
+        if let Some((_, new)) = migration.renamed.iter().find(|(old, _)| *old == name) {
+            name = new.to_string();
+        }
+        if let Some((_, convert)) = migration.converted.iter().find(|(n, _)| *n == name) {
+            bytes = convert(backend, bytes).map_err(|e| format!("{}: {}", name, e))?;
+        }
+    }
+    Ok(Some((name, bytes)))
+}
+
+/// Add all components in `storage` to the save, under `name`
+fn write_component<S, C, D>(
+    map: &mut S,
+    name: &str,
+    storage: &Storage<C, D>,
+    markers: &ReadStorage<U64Marker>,
+    backend: SaveBackend,
+) -> Result<(), S::Error>
+where
+    S: SerializeMap,
+    C: Component + ConvertSaveload<U64Marker, Error = NoError>,
+    D: Deref<Target = MaskedStorage<C>>,
+{
+    let mut entries = vec![];
+    for (marker, component) in (markers, storage).join() {
+        let data = match component.convert_into(|entity| markers.get(entity).cloned()) {
+            Ok(data) => data,
+            Err(e) => match e {},
+        };
+        entries.push((*marker, data));
+    }
+    let bytes = backend.encode_entries(&entries).map_err(S::Error::custom)?;
+    map.serialize_entry(name, &EncodedEntries { backend, bytes })
+}
+
+/// Read the components written by `write_component`, creating entities for them as needed
+fn read_component<C>(
+    bytes: &[u8],
+    backend: SaveBackend,
+    storage: &mut WriteStorage<C>,
+    markers: &mut WriteStorage<U64Marker>,
+    allocator: &mut U64MarkerAllocator,
+    entities: &EntitiesRes,
+) -> Result<(), String>
+where
+    C: Component + ConvertSaveload<U64Marker, Error = NoError>,
+{
+    let entries: Vec<(U64Marker, C::Data)> = backend.decode_entries(bytes)?;
+    for (marker, data) in entries {
+        let entity = allocator.retrieve_entity(marker, markers, entities);
+        let component = match C::convert_from(data, |marker| {
+            Some(allocator.retrieve_entity(marker, markers, entities))
+        }) {
+            Ok(component) => component,
+            Err(e) => match e {},
+        };
+        storage
+            .insert(entity, component)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// For counting things in macros
+macro_rules! one {
+    ($x:tt) => {
+        1
+    };
+}
+
+/// Declares which components end up in a save, and the names they're saved under. Generates
+/// `SavedStorages` and `LoadedStorages` to read and write all of them at once. Anything listed
+/// here that can't be serialized won't compile.
+macro_rules! saved_components {
+    ($($field:ident: $component:ty = $name:literal,)*) => {
+        #[derive(SystemData)]
+        pub struct SavedStorages<'a> {
+            $(pub $field: ReadStorage<'a, $component>,)*
+        }
+
+        impl<'a> SavedStorages<'a> {
+            pub const COUNT: usize = 0 $(+ one!($name))*;
+
+            pub fn write<S: SerializeMap>(
+                &self,
+                map: &mut S,
+                markers: &ReadStorage<U64Marker>,
+                backend: SaveBackend,
+            ) -> Result<(), S::Error> {
+                $(write_component(map, $name, &self.$field, markers, backend)?;)*
+                Ok(())
+            }
+        }
+
+        #[derive(SystemData)]
+        pub struct LoadedStorages<'a> {
+            $(pub $field: WriteStorage<'a, $component>,)*
+        }
+
+        impl<'a> LoadedStorages<'a> {
+            /// Read the components saved under `name` from their encoded entries,
+            /// or return false if we don't know them
+            pub fn read(
+                &mut self,
+                name: &str,
+                bytes: &[u8],
+                backend: SaveBackend,
+                markers: &mut WriteStorage<U64Marker>,
+                allocator: &mut U64MarkerAllocator,
+                entities: &EntitiesRes,
+            ) -> Result<bool, String> {
+                match name {
+                    $($name => read_component(
+                        bytes,
+                        backend,
+                        &mut self.$field,
+                        markers,
+                        allocator,
+                        entities,
+                    )?,)*
+                    _ => return Ok(false),
+                }
+                Ok(true)
+            }
+        }
+    };
+}
+
+saved_components! {
+    collider: Collider = "Collider",
+    confused: Confused = "Confused",
+    inventory: Inventory = "Inventory",
+    item: Item = "Item",
+    living: Living = "Living",
+    map: Map = "Map",
+    messages: Messages = "Messages",
+    name: Name = "Name",
+    player: Player = "Player",
+    position: Position = "Position",
+    previous_position: PreviousPosition = "PreviousPosition",
+    power: Power = "Power",
+    synthetic: Synthetic = "Synthetic",
+    velocity: Velocity = "Velocity",
+    visual: Visual = "Visual",
+    energy: Energy = "Energy",
+    action: Action = "Action",
+    ai: Ai = "Ai",
+    identification: Identification = "Identification",
+    weight: Weight = "Weight",
+    launcher: Launcher = "Launcher",
+    ammo: Ammo = "Ammo",
+    thrown_damage: ThrownDamage = "ThrownDamage",
+    trap: Trap = "Trap",
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// A gzipped binary save with `body` after the magic bytes
+    fn binary_save(body: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(BINARY_MAGIC.to_vec(), Compression::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn read_header(bytes: &[u8]) -> Result<SaveHeader, SaveError> {
+        read_save(bytes, HeaderOnly)
+    }
+
+    #[test]
+    fn binary_header_round_trip() {
+        let bytes = SaveBackend::Binary
+            .encode(&SaveHeader::current("Rodney", 4, 120), &())
+            .unwrap();
+        let header = read_header(&bytes).unwrap();
+        assert_eq!(header.format_version, FORMAT_VERSION);
+        assert_eq!((header.depth, header.turns), (4, 120));
+    }
+
+    #[test]
+    fn huge_length_in_binary_save_is_an_error() {
+        // the format version, then a game version string claiming to be a terabyte long
+        let mut body = vec![FORMAT_VERSION as u8, 253];
+        body.extend_from_slice(&(1u64 << 40).to_le_bytes());
+        match read_header(&binary_save(&body)) {
+            Err(SaveError::Corrupt(_)) => (),
+            Err(e) => panic!("wrong error: {}", e),
+            Ok(_) => panic!("read a corrupt save"),
+        }
+    }
+
+    #[test]
+    fn truncated_binary_save_is_an_error() {
+        let bytes = SaveBackend::Binary
+            .encode(&SaveHeader::current("Rodney", 4, 120), &())
+            .unwrap();
+        let mut de = GzDecoder::new(&bytes[BINARY_MAGIC.len()..]);
+        let mut body = vec![];
+        std::io::Read::read_to_end(&mut de, &mut body).unwrap();
+        body.truncate(body.len() / 2);
+        assert!(read_header(&binary_save(&body)).is_err());
+    }
+
+    fn header_with_version(format_version: u32) -> SaveHeader {
+        SaveHeader {
+            format_version,
+            ..SaveHeader::current("Rodney", 1, 1)
+        }
+    }
+
+    #[test]
+    fn current_version_is_accepted() {
+        assert!(header_with_version(FORMAT_VERSION).check().is_ok());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        match header_with_version(FORMAT_VERSION + 1).check() {
+            Err(SaveError::Incompatible(_)) => (),
+            other => panic!("expected an incompatible save, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn older_version_is_rejected() {
+        match header_with_version(OLDEST_FORMAT_VERSION - 1).check() {
+            Err(SaveError::Incompatible(_)) => (),
+            other => panic!("expected an incompatible save, got {:?}", other),
+        }
+    }
+
+    /// How `Health` used to be saved
+    #[derive(Debug, Serialize, Deserialize)]
+    struct OldHealth(i32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health {
+        hp: i32,
+        max_hp: i32,
+    }
+
+    fn convert_health(backend: SaveBackend, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        backend.convert_entries(&bytes, |OldHealth(hp)| Health { hp, max_hp: hp })
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 2,
+            renamed: &[("Hp", "Health")],
+            removed: &["Hunger"],
+            converted: &[],
+        },
+        Migration {
+            version: 3,
+            renamed: &[],
+            removed: &[],
+            converted: &[("Health", convert_health as Convert)],
+        },
+    ];
+
+    fn migrate_old_health(
+        backend: SaveBackend,
+        version: u32,
+    ) -> (String, Vec<(U64Marker, Health)>) {
+        let old = vec![(U64Marker(1), OldHealth(7))];
+        let bytes = backend.encode_entries(&old).unwrap();
+        let (name, bytes) =
+            migrate_with(TEST_MIGRATIONS, version, "Hp".to_string(), bytes, backend)
+                .unwrap()
+                .unwrap();
+        (name, backend.decode_entries(&bytes).unwrap())
+    }
+
+    #[test]
+    fn migration_renames_and_converts() {
+        for &backend in &[SaveBackend::Binary, SaveBackend::Ron] {
+            let (name, entries) = migrate_old_health(backend, 1);
+            assert_eq!(name, "Health");
+            assert_eq!(entries, vec![(U64Marker(1), Health { hp: 7, max_hp: 7 })]);
+        }
+    }
+
+    #[test]
+    fn migration_skips_removed_components() {
+        let bytes = SaveBackend::Binary
+            .encode_entries(&vec![(U64Marker(1), 3)])
+            .unwrap();
+        let migrated = migrate_with(
+            TEST_MIGRATIONS,
+            1,
+            "Hunger".to_string(),
+            bytes,
+            SaveBackend::Binary,
+        );
+        assert_eq!(migrated, Ok(None));
+    }
+
+    #[test]
+    fn migrations_already_applied_are_not_run_again() {
+        let bytes = SaveBackend::Ron
+            .encode_entries(&vec![(U64Marker(1), 3)])
+            .unwrap();
+        let migrated = migrate_with(
+            TEST_MIGRATIONS,
+            3,
+            "Hp".to_string(),
+            bytes.clone(),
+            SaveBackend::Ron,
+        );
+        assert_eq!(migrated, Ok(Some(("Hp".to_string(), bytes))));
+    }
+
+    #[test]
+    fn failed_conversion_is_an_error() {
+        let bytes = SaveBackend::Ron.encode_entries(&"not a list").unwrap();
+        let migrated = migrate_with(
+            TEST_MIGRATIONS,
+            2,
+            "Health".to_string(),
+            bytes,
+            SaveBackend::Ron,
+        );
+        assert!(migrated.is_err());
+    }
+}
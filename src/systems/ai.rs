@@ -16,6 +16,7 @@ use crate::{
 #[derive(SystemData)]
 pub struct AISystemData<'a> {
     ai: WriteStorage<'a, Ai>,
+    confused: WriteStorage<'a, Confused>,
     living: ReadStorage<'a, Living>,
     player: ReadStorage<'a, Player>,
     name: ReadStorage<'a, Name>,
@@ -58,7 +59,14 @@ impl<'a> System<'a> for AISystem {
             (&data.living, &data.position, &data.ai, &data.entity)
                 .join()
                 .filter(|j| j.0.alive)
-                .map(|j| (j.3, fov_map.is_in_fov(j.1.x, j.1.y)))
+                .map(|j| {
+                    // Alerted monsters come for the player even if they can't see them
+                    let alerted = match j.2 {
+                        Ai::Alerted { .. } => true,
+                        _ => false,
+                    };
+                    (j.3, alerted || fov_map.is_in_fov(j.1.x, j.1.y))
+                })
                 .collect()
         };
 
@@ -75,7 +83,7 @@ impl<'a> System<'a> for AISystem {
 
 impl Distribution<Heading> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Heading {
-        match rng.gen_range(0, 3) {
+        match rng.gen_range(0, 4) {
             0 => Heading::North,
             1 => Heading::East,
             2 => Heading::South,
@@ -90,7 +98,8 @@ fn run_ai(entity: Entity, data: &mut AISystemData) -> Action {
     match ai {
         Ai::Basic => basic_ai(entity, data),
         Ai::Confused { .. } => confused_ai(entity, data),
-        Ai::Player => player_ai(data),
+        Ai::Alerted { .. } => alerted_ai(entity, data),
+        Ai::Player => player_ai(entity, data),
     }
 }
 
@@ -137,6 +146,25 @@ fn confused_ai(entity: Entity, data: &mut AISystemData) -> Action {
     }
 }
 
+fn alerted_ai(entity: Entity, data: &mut AISystemData) -> Action {
+    let ai = data.ai.get_mut(entity).unwrap();
+    match ai {
+        Ai::Alerted {
+            ref mut num_turns,
+            previous_ai,
+        } => {
+            *num_turns -= 1;
+            if *num_turns == 0 {
+                *ai = *previous_ai.clone();
+                run_ai(entity, data)
+            } else {
+                basic_ai(entity, data)
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
 fn player_move_or_melee(heading: Heading) -> Action {
     Action::MoveOrMelee {
         velocity: Velocity::from(heading),
@@ -145,18 +173,50 @@ fn player_move_or_melee(heading: Heading) -> Action {
     }
 }
 
-fn player_ai(data: &mut AISystemData) -> Action {
+fn player_ai(entity: Entity, data: &mut AISystemData) -> Action {
     use crate::resources::input_action::InputAction::*;
     let action = match *data.input_action {
         MoveNorth => player_move_or_melee(Heading::North),
         MoveEast => player_move_or_melee(Heading::East),
         MoveSouth => player_move_or_melee(Heading::South),
         MoveWest => player_move_or_melee(Heading::West),
-        Drop(n) => Action::Drop { inventory_index: n },
+        Drop(ref indices) => Action::Drop {
+            inventory_indices: indices.clone(),
+        },
         UseFromInventory(n) => Action::UseFromInventory { inventory_index: n },
         PickUp => Action::PickUp,
+        CloseDoor => Action::CloseDoor,
+        Search => Action::Search,
+        Disarm => Action::Disarm,
+        FireAt(x, y) => Action::Fire {
+            target: Position { x, y },
+        },
+        ThrowAt(n, x, y) => Action::Throw {
+            inventory_index: n,
+            target: Position { x, y },
+        },
         _ => Action::WaitForInput,
     };
     *data.input_action = InputAction::Noop;
-    action
+    if action == Action::WaitForInput {
+        return action;
+    }
+
+    // a confused player doesn't get to choose where they go
+    let num_turns = match data.confused.get_mut(entity) {
+        Some(Confused(num_turns)) => {
+            *num_turns -= 1;
+            *num_turns
+        }
+        None => return action,
+    };
+    if num_turns <= 0 {
+        data.confused.remove(entity);
+        data.messages
+            .push("You are no longer confused.", colors::LIGHT_GREEN);
+    }
+    match action {
+        Action::MoveOrMelee { .. } => player_move_or_melee(rand::thread_rng().gen()),
+        other => other,
+    }
 }
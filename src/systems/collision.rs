@@ -2,7 +2,7 @@ use shred_derive::SystemData;
 use specs::prelude::*;
 
 use crate::{
-    components::{Collider, Position, Velocity},
+    components::{Collider, Player, Position, Velocity},
     resources::{
         map::{CalculateBlockedMapExt, Map},
         state::State,
@@ -14,6 +14,7 @@ pub struct CollisionSystem;
 #[derive(SystemData)]
 pub struct CollisionSystemData<'a> {
     collider: ReadStorage<'a, Collider>,
+    player: ReadStorage<'a, Player>,
     position: ReadStorage<'a, Position>,
     velocity: WriteStorage<'a, Velocity>,
 
@@ -29,18 +30,19 @@ impl<'a> System<'a> for CollisionSystem {
             return;
         }
 
-        for (pos, mut vel) in (&data.position, &mut data.velocity).join() {
+        for (pos, mut vel, player) in
+            (&data.position, &mut data.velocity, (&data.player).maybe()).join()
+        {
             if vel.magnitude == 0 {
                 continue;
             }
             let candidate = &*pos + &*vel;
-            let blocked = data
-                .map
-                .as_ref()
-                .unwrap()
-                .is_blocked(&candidate, (&data.position, &data.collider).join());
+            let map = data.map.as_ref().unwrap();
+            let blocked = map.is_blocked(&candidate, (&data.position, &data.collider).join());
+            // Monsters don't walk into lava and the like; the player is free to make mistakes
+            let avoided = player.is_none() && map[&candidate].kind.is_hazardous();
             // If something blocks the movement, reject the whole thing
-            if blocked {
+            if blocked || avoided {
                 vel.magnitude = 0;
             }
         }
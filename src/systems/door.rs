@@ -0,0 +1,78 @@
+use std::sync::{Arc, Mutex};
+
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::{colors, map::Map as FovMap};
+
+use crate::{
+    components::{velocity::Heading, *},
+    resources::{map::Map, messages::Messages, state::State},
+    systems::fov::update_fov_tile,
+};
+
+pub struct DoorSystem;
+
+#[derive(SystemData)]
+pub struct DoorSystemData<'a> {
+    action: ReadStorage<'a, Action>,
+    energy: WriteStorage<'a, Energy>,
+    player: ReadStorage<'a, Player>,
+    position: ReadStorage<'a, Position>,
+
+    map: Option<WriteExpect<'a, Map>>,
+    fov_map: Option<ReadExpect<'a, Arc<Mutex<FovMap>>>>,
+    messages: Write<'a, Messages>,
+    state: ReadExpect<'a, State>,
+}
+
+impl<'a> System<'a> for DoorSystem {
+    type SystemData = DoorSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        for (action, energy, position, player) in (
+            &data.action,
+            &mut data.energy,
+            &data.position,
+            (&data.player).maybe(),
+        )
+            .join()
+            .filter(|j| *j.0 == Action::CloseDoor)
+        {
+            let map = data.map.as_mut().unwrap();
+
+            // Close the first open door next to the actor that's not blocked by anything
+            let door = [Heading::North, Heading::East, Heading::South, Heading::West]
+                .iter()
+                .map(|heading| position + &Velocity::unit(heading.clone()))
+                .find(|candidate| {
+                    map[candidate].is_open_door()
+                        && (&data.position).join().all(|other| other != candidate)
+                });
+
+            match door {
+                Some(door) => {
+                    if !energy.consume(action.energy_cost()) {
+                        continue;
+                    }
+                    map[&door].close_door();
+                    let fov_map_mutex = data.fov_map.as_ref().unwrap().clone();
+                    let fov_map = &mut *fov_map_mutex.lock().unwrap();
+                    update_fov_tile(fov_map, &door, &map[&door]);
+                    if player.is_some() {
+                        data.messages.push("You close the door.", colors::WHITE);
+                    }
+                }
+                None => {
+                    if player.is_some() {
+                        data.messages
+                            .push("There's no open door next to you.", colors::WHITE);
+                    }
+                }
+            }
+        }
+    }
+}
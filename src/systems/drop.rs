@@ -2,7 +2,7 @@ use shred_derive::SystemData;
 use specs::prelude::*;
 
 use crate::components::*;
-use crate::resources::messages::Messages;
+use crate::resources::{identification::Identification, messages::Messages};
 use tcod::colors;
 
 pub struct DropSystem;
@@ -14,9 +14,11 @@ pub struct DropSystemData<'a> {
     player: ReadStorage<'a, Player>,
     action: ReadStorage<'a, Action>,
     name: ReadStorage<'a, Name>,
+    item: ReadStorage<'a, Item>,
     energy: WriteStorage<'a, Energy>,
 
     messages: Write<'a, Messages>,
+    identification: Read<'a, Identification>,
 
     entity: Entities<'a>,
 }
@@ -34,18 +36,32 @@ impl<'a> System<'a> for DropSystem {
             .join()
             .map(|j| (j.0, j.1, j.2, j.3))
         {
-            match *action {
-                Action::Drop { inventory_index } => {
-                    if let Some(&entity) = { inventory.0.get(inventory_index) } {
-                        if energy.consume(action.energy_cost()) {
-                            let position = { data.position.get(actor).unwrap().clone() };
-                            data.position.insert(entity, position).unwrap();
-                            inventory.0.remove(inventory_index);
-                            data.messages.push(
-                                format!("You dropped a {}.", data.name.get(entity).unwrap().0),
-                                colors::YELLOW,
-                            );
-                        }
+            match action {
+                Action::Drop { inventory_indices } => {
+                    let mut indices: Vec<_> = inventory_indices
+                        .iter()
+                        .cloned()
+                        .filter(|index| inventory.get(*index).is_some())
+                        .collect();
+                    if indices.is_empty() || !energy.consume(action.energy_cost()) {
+                        continue;
+                    }
+                    // Only a single item of each stack is dropped, the rest of it stays put.
+                    // Going from the back, so emptied stacks don't shift the ones still to go.
+                    indices.sort_unstable_by(|a, b| b.cmp(a));
+                    indices.dedup();
+                    for index in indices {
+                        let entity = inventory.take(index).unwrap();
+                        let position = { data.position.get(actor).unwrap().clone() };
+                        data.position.insert(entity, position).unwrap();
+                        data.messages.push(
+                            format!(
+                                "You dropped a {}.",
+                                data.identification
+                                    .name_of(data.item.get(entity), data.name.get(entity).unwrap())
+                            ),
+                            colors::YELLOW,
+                        );
                     }
                 }
                 _ => (),
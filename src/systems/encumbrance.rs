@@ -0,0 +1,51 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{components::*, resources::messages::Messages};
+
+pub struct EncumbranceSystem;
+
+#[derive(SystemData)]
+pub struct EncumbranceSystemData<'a> {
+    inventory: ReadStorage<'a, Inventory>,
+    weight: ReadStorage<'a, Weight>,
+    player: ReadStorage<'a, Player>,
+    burden: WriteStorage<'a, Burden>,
+
+    entity: Entities<'a>,
+    messages: Write<'a, Messages>,
+}
+
+impl<'a> System<'a> for EncumbranceSystem {
+    type SystemData = EncumbranceSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        for (entity, inventory) in (&data.entity, &data.inventory).join() {
+            let carried: i32 = inventory
+                .items()
+                .map(|item| data.weight.get(*item).map_or(0, |w| w.0))
+                .sum();
+            let burden = Burden::from_weight(carried, inventory.weight_limit);
+            let previous = data
+                .burden
+                .get(entity)
+                .cloned()
+                .unwrap_or(Burden::Unburdened);
+            if burden == previous && data.burden.get(entity).is_some() {
+                continue;
+            }
+
+            if data.player.get(entity).is_some() && burden != previous {
+                if burden.energy_multiplier() > previous.energy_multiplier() {
+                    data.messages
+                        .push("Your load slows you down.", colors::YELLOW);
+                } else {
+                    data.messages
+                        .push("Your load feels lighter.", colors::LIGHT_GREEN);
+                }
+            }
+            data.burden.insert(entity, burden).unwrap();
+        }
+    }
+}
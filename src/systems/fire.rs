@@ -0,0 +1,147 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{
+    components::*,
+    resources::{map::Map, messages::Messages, state::State},
+    systems::projectile::fly,
+};
+
+pub struct FireSystem;
+
+#[derive(SystemData)]
+pub struct FireSystemData<'a> {
+    action: ReadStorage<'a, Action>,
+    ammo: ReadStorage<'a, Ammo>,
+    collider: ReadStorage<'a, Collider>,
+    energy: WriteStorage<'a, Energy>,
+    inventory: WriteStorage<'a, Inventory>,
+    launcher: ReadStorage<'a, Launcher>,
+    living: WriteStorage<'a, Living>,
+    name: ReadStorage<'a, Name>,
+    position: WriteStorage<'a, Position>,
+
+    entity: Entities<'a>,
+    map: Option<ReadExpect<'a, Map>>,
+    messages: Write<'a, Messages>,
+    state: ReadExpect<'a, State>,
+}
+
+impl<'a> System<'a> for FireSystem {
+    type SystemData = FireSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        let shots: Vec<(Entity, Position)> = (&data.entity, &data.action)
+            .join()
+            .filter_map(|(entity, action)| match action {
+                Action::Fire { target } => Some((entity, target.clone())),
+                _ => None,
+            })
+            .collect();
+
+        for (shooter, target) in shots {
+            fire(shooter, &target, &mut data);
+        }
+    }
+}
+
+fn fire(shooter: Entity, target: &Position, data: &mut FireSystemData) {
+    let origin = match data.position.get(shooter) {
+        Some(position) => position.clone(),
+        None => return,
+    };
+
+    // Find something to shoot, and something to shoot it with
+    let (launcher, ammo_index) = {
+        let inventory = match data.inventory.get(shooter) {
+            Some(inventory) => inventory,
+            None => return,
+        };
+        let launcher = match inventory
+            .stacks
+            .iter()
+            .map(|stack| stack.top())
+            .find(|item| data.launcher.get(*item).is_some())
+        {
+            Some(launcher) => launcher,
+            None => return,
+        };
+        let ammo_kind = data.launcher.get(launcher).unwrap().ammo;
+        match inventory
+            .find_stack(|item| data.ammo.get(item).map_or(false, |a| a.kind == ammo_kind))
+        {
+            Some(index) => (launcher, index),
+            None => {
+                data.messages
+                    .push("You are out of ammunition.", colors::WHITE);
+                return;
+            }
+        }
+    };
+
+    let action = data.action.get(shooter).unwrap().clone();
+    if !data
+        .energy
+        .get_mut(shooter)
+        .map_or(false, |energy| energy.consume(action.energy_cost()))
+    {
+        return;
+    }
+
+    let missile = data
+        .inventory
+        .get_mut(shooter)
+        .unwrap()
+        .take(ammo_index)
+        .unwrap();
+    let damage =
+        data.launcher.get(launcher).unwrap().damage + data.ammo.get(missile).unwrap().damage;
+
+    // Follow the projectile until it hits something or reaches the target
+    let blockers: Vec<(Entity, Position)> =
+        (&data.entity, &data.position, &data.collider, &data.living)
+            .join()
+            .filter(|j| j.3.alive)
+            .map(|j| (j.0, j.1.clone()))
+            .collect();
+    let flight = fly(data.map.as_ref().unwrap(), &origin, target, &blockers);
+    let missile_name = data.name.get(missile).unwrap().0.clone();
+
+    if flight.hit_wall {
+        data.messages.push(
+            format!("The {} hits the wall.", missile_name),
+            colors::WHITE,
+        );
+    }
+    if let Some(victim) = flight.hit {
+        let living = data.living.get_mut(victim).unwrap();
+        let victim_name = &data.name.get(victim).unwrap().0;
+        let damage = damage - living.defense;
+        if damage > 0 {
+            data.messages.push(
+                format!(
+                    "The {} hits {} for {} hit points.",
+                    missile_name, victim_name, damage
+                ),
+                colors::WHITE,
+            );
+            living.take_damage(damage, format!("the {}", missile_name), true);
+        } else {
+            data.messages.push(
+                format!(
+                    "The {} hits {} but it has no effect!",
+                    missile_name, victim_name
+                ),
+                colors::WHITE,
+            );
+        }
+    }
+
+    // Whatever happened, the ammo ends up on the floor, ready to be picked up again
+    data.position.insert(missile, flight.landing).unwrap();
+}
@@ -4,10 +4,7 @@ use shred_derive::SystemData;
 use specs::prelude::*;
 use tcod::map::Map as FovMap;
 
-use crate::resources::{
-    map::{Map, MAP_HEIGHT, MAP_WIDTH},
-    state::State,
-};
+use crate::resources::{map::Map, state::State};
 
 #[derive(SystemData)]
 pub struct FogOfWarSystemData<'a> {
@@ -27,10 +24,11 @@ impl<'a> System<'a> for FogOfWarSystem {
         }
         let fov_map_mutex = data.fov_map.as_ref().unwrap().clone();
         let fov_map = fov_map_mutex.lock().unwrap();
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
+        let map = data.map.as_mut().unwrap();
+        for y in 0..map.height {
+            for x in 0..map.width {
                 if fov_map.is_in_fov(x, y) {
-                    data.map.as_mut().unwrap().tiles[x as usize][y as usize].explored = true;
+                    map.tiles[x as usize][y as usize].explored = true;
                 }
             }
         }
@@ -7,7 +7,7 @@ use tcod::map::{FovAlgorithm, Map as FovMap};
 use crate::{
     components::{Player, Position, PreviousPosition},
     resources::{
-        map::{Tiles, MAP_HEIGHT, MAP_WIDTH},
+        map::{Map, Tile},
         state::State,
     },
 };
@@ -46,17 +46,21 @@ impl<'a> System<'a> for FovSystem {
     }
 }
 
-pub fn new_fov_map(map: &Tiles) -> Arc<Mutex<FovMap>> {
-    let mut fov_map = FovMap::new(MAP_WIDTH, MAP_HEIGHT);
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
-            fov_map.set(
-                x,
-                y,
-                !map[x as usize][y as usize].block_sight,
-                !map[x as usize][y as usize].blocked,
+pub fn new_fov_map(map: &Map) -> Arc<Mutex<FovMap>> {
+    let mut fov_map = FovMap::new(map.width, map.height);
+    for y in 0..map.height {
+        for x in 0..map.width {
+            update_fov_tile(
+                &mut fov_map,
+                &Position { x, y },
+                &map.tiles[x as usize][y as usize],
             );
         }
     }
     Arc::new(Mutex::new(fov_map))
 }
+
+/// Needs to be called whenever a tile changes at runtime (for example a door opens)
+pub fn update_fov_tile(fov_map: &mut FovMap, position: &Position, tile: &Tile) {
+    fov_map.set(position.x, position.y, !tile.block_sight(), !tile.blocked());
+}
@@ -0,0 +1,80 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+
+use crate::{
+    resources::{
+        input_action::InputAction,
+        keymap::{action_category, describe_action, Keymap, CATEGORIES},
+        menu::{Menu, MenuResult},
+    },
+    systems::input::{menu_keys, targeting_keys},
+};
+
+/// Shows what all the keys do, as they're bound right now
+pub struct HelpSystem;
+
+const HELP_WIDTH: i32 = 70;
+/// Where the keys start on each line, after the name of what they do
+const KEYS_COLUMN: usize = 26;
+
+#[derive(SystemData)]
+pub struct HelpSystemData<'a> {
+    input_action: WriteExpect<'a, InputAction>,
+    keymap: Read<'a, Keymap>,
+    menu: WriteExpect<'a, Option<Menu>>,
+}
+
+fn section(text: &mut String, heading: &str, lines: &[(String, String)]) {
+    text.push_str(&format!("{}\n", heading));
+    for (name, keys) in lines {
+        text.push_str(&format!(
+            "  {:<width$}{}\n",
+            name,
+            keys,
+            width = KEYS_COLUMN
+        ));
+    }
+    text.push('\n');
+}
+
+fn help_text(keymap: &Keymap) -> String {
+    let mut text = String::new();
+    for category in CATEGORIES {
+        let lines: Vec<_> = keymap
+            .bindings
+            .iter()
+            .filter(|(action, _)| action_category(action) == *category)
+            .map(|(action, keys)| {
+                (
+                    describe_action(action).to_string(),
+                    Keymap::describe_keys(keys),
+                )
+            })
+            .collect();
+        section(&mut text, category, &lines);
+    }
+
+    // these can't be rebound, so the keys go first
+    section(&mut text, "In menus", &menu_keys());
+    section(&mut text, "While targeting", &targeting_keys());
+    text.push_str("Press escape to close this.\n");
+    text
+}
+
+impl<'a> System<'a> for HelpSystem {
+    type SystemData = HelpSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.input_action != InputAction::ShowHelp {
+            return;
+        }
+
+        *data.menu = Some(Menu::new(
+            help_text(&data.keymap),
+            HELP_WIDTH,
+            vec![],
+            |_| MenuResult::Back,
+        ));
+        *data.input_action = InputAction::Noop;
+    }
+}
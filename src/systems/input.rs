@@ -3,17 +3,24 @@ use std::sync::{Arc, Mutex};
 use shred_derive::SystemData;
 use specs::prelude::*;
 use tcod::{
-    input::{Key, KeyCode::*, Mouse},
+    colors,
+    input::{
+        Key,
+        KeyCode::{self, *},
+        Mouse,
+    },
     map::Map as FovMap,
 };
 
 use crate::{
     components::*,
     resources::{
+        camera::Camera,
         input_action::InputAction::{self, *},
-        menu::{Menu, MenuKind},
+        keymap::{KeyBinding, Keymap},
+        menu::{menu_index, Menu, MENU_PAGE_SIZE},
         messages::Messages,
-        targeting::Targeting,
+        targeting::{Targeting, TargetingIntent, TargetingKind},
         ui::UIState,
     },
 };
@@ -28,12 +35,16 @@ pub struct InputSystemData<'a> {
     living: WriteStorage<'a, Living>,
     player: ReadStorage<'a, Player>,
     item: ReadStorage<'a, Item>,
+    launcher: ReadStorage<'a, Launcher>,
+    ammo: ReadStorage<'a, Ammo>,
     velocity: WriteStorage<'a, Velocity>,
     inventory: WriteStorage<'a, Inventory>,
     position: WriteStorage<'a, Position>,
 
     entity: Entities<'a>,
 
+    camera: Read<'a, Camera>,
+    keymap: Read<'a, Keymap>,
     menu: WriteExpect<'a, Option<Menu>>,
     ui: WriteExpect<'a, UIState>,
     action: WriteExpect<'a, InputAction>,
@@ -42,133 +53,404 @@ pub struct InputSystemData<'a> {
     targeting: WriteExpect<'a, Option<Targeting>>,
 }
 
+/// What a named key does in a menu
+#[derive(Clone, Copy, PartialEq)]
+enum MenuKey {
+    Previous,
+    Next,
+    PreviousPage,
+    NextPage,
+    Tick,
+    Choose,
+    Erase,
+    Dismiss,
+}
+
+/// The named keys `handle_menu_input` knows. The help screen lists them in this order.
+const MENU_KEY_CODES: &[(KeyCode, MenuKey)] = &[
+    (Up, MenuKey::Previous),
+    (Down, MenuKey::Next),
+    (PageUp, MenuKey::PreviousPage),
+    (PageDown, MenuKey::NextPage),
+    (Spacebar, MenuKey::Tick),
+    (Enter, MenuKey::Choose),
+    (NumPadEnter, MenuKey::Choose),
+    (Backspace, MenuKey::Erase),
+    (Escape, MenuKey::Dismiss),
+];
+
+impl MenuKey {
+    fn of(code: KeyCode) -> Option<MenuKey> {
+        MENU_KEY_CODES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, key)| *key)
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            MenuKey::Previous => "Highlight the previous entry",
+            MenuKey::Next => "Highlight the next entry",
+            MenuKey::PreviousPage => "Show the previous page",
+            MenuKey::NextPage => "Show the next page",
+            MenuKey::Tick => "Tick the highlighted entry",
+            MenuKey::Choose => "Pick the highlighted or ticked entries",
+            MenuKey::Erase => "Delete the last character typed",
+            MenuKey::Dismiss => "Go back",
+        }
+    }
+}
+
+/// The keys `handle_menu_input` knows, for the help screen
+pub fn menu_keys() -> Vec<(String, String)> {
+    let mut keys = vec![
+        ("a-z".to_string(), "Pick an entry, or tick it".to_string()),
+        (
+            "Typing".to_string(),
+            "Enter text, where a name is asked for".to_string(),
+        ),
+        (
+            "Mouse".to_string(),
+            "Highlight an entry, click to pick it".to_string(),
+        ),
+    ];
+    keys.extend(describe_table(
+        MENU_KEY_CODES,
+        |code| format!("{:?}", code),
+        MenuKey::describe,
+    ));
+    keys
+}
+
+/// What a mouse button or key does while picking a target
+#[derive(Clone, Copy, PartialEq)]
+enum TargetingKey {
+    Pick,
+    Cancel,
+}
+
+/// Something the player can press while picking a target
+#[derive(Clone, Copy)]
+enum TargetingInput {
+    LeftClick,
+    RightClick,
+    Key(KeyCode),
+}
+
+/// The inputs `pick_target` knows. The help screen lists them in this order.
+const TARGETING_INPUTS: &[(TargetingInput, TargetingKey)] = &[
+    (TargetingInput::LeftClick, TargetingKey::Pick),
+    (TargetingInput::RightClick, TargetingKey::Cancel),
+    (TargetingInput::Key(Escape), TargetingKey::Cancel),
+];
+
+impl TargetingInput {
+    fn pressed(self, mouse: &Mouse, key: Option<&Key>) -> bool {
+        match self {
+            TargetingInput::LeftClick => mouse.lbutton_pressed,
+            TargetingInput::RightClick => mouse.rbutton_pressed,
+            TargetingInput::Key(code) => key.map_or(false, |k| k.code == code),
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            TargetingInput::LeftClick => "Left click".to_string(),
+            TargetingInput::RightClick => "Right click".to_string(),
+            TargetingInput::Key(code) => format!("{:?}", code),
+        }
+    }
+}
+
+impl TargetingKey {
+    /// Whether any of the inputs for this were pressed
+    fn pressed(self, mouse: &Mouse, key: Option<&Key>) -> bool {
+        TARGETING_INPUTS
+            .iter()
+            .any(|(input, k)| *k == self && input.pressed(mouse, key))
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            TargetingKey::Pick => "Pick the target",
+            TargetingKey::Cancel => "Cancel",
+        }
+    }
+}
+
+/// The inputs `pick_target` knows, for the help screen
+pub fn targeting_keys() -> Vec<(String, String)> {
+    describe_table(
+        TARGETING_INPUTS,
+        TargetingInput::name,
+        TargetingKey::describe,
+    )
+}
+
+/// A line for each thing in `table`, with all the inputs that do it
+fn describe_table<I: Copy, K: Copy + PartialEq>(
+    table: &[(I, K)],
+    name: impl Fn(I) -> String,
+    describe: impl Fn(K) -> &'static str,
+) -> Vec<(String, String)> {
+    let mut lines = vec![];
+    let mut described: Vec<K> = vec![];
+    for (_, key) in table {
+        if described.contains(key) {
+            continue;
+        }
+        described.push(*key);
+        let inputs: Vec<_> = table
+            .iter()
+            .filter(|(_, k)| k == key)
+            .map(|(input, _)| name(*input))
+            .collect();
+        lines.push((inputs.join(", "), describe(*key).to_string()));
+    }
+    lines
+}
+
 pub struct InputSystem;
 
 impl InputSystem {
     fn handle_game_input(mut data: InputSystemData) {
-        *data.action = if let Some(k) = data.key.as_ref() {
-            match k {
-                Key {
-                    code: Enter,
-                    alt: true,
-                    ..
-                } => ToggleFullScreen,
-                Key { code: Escape, .. } => MainMenu,
-                Key { code: Up, .. } => MoveNorth,
-                Key { code: Right, .. } => MoveEast,
-                Key { code: Down, .. } => MoveSouth,
-                Key { code: Left, .. } => MoveWest,
-                Key { code: Text, .. } => match k.text() {
-                    "i" => OpenInventoryMenu,
-                    "g" => PickUp,
-                    "d" => OpenDropMenu,
-                    ">" => MoveDown,
-                    _ => Noop,
-                },
-                _ => Noop,
-            }
-        } else {
-            Noop
-        };
+        *data.action = data
+            .key
+            .as_ref()
+            .and_then(KeyBinding::from_key)
+            .and_then(|key| data.keymap.action_for(&key))
+            .unwrap_or(Noop);
+
+        if *data.action == Fire {
+            *data.action = InputSystem::start_firing(&mut data);
+        }
 
         *data.key = None;
     }
 
     fn handle_menu_input(mut data: InputSystemData) {
-        let menu = data.menu.as_ref().unwrap();
-
-        *data.action = if let Some(k) = data.key.as_ref() {
-            match k {
-                Key { code: Escape, .. } => DismissMenu,
-                Key { code: Text, .. } => {
-                    let c = k.text().chars().next();
-                    if !c.map_or_else(|| false, |x| x.is_ascii_alphanumeric()) {
-                        Noop
+        let menu = data.menu.as_mut().unwrap();
+        let page = menu.page_range();
+        // Picking an entry ticks it instead in multi-select menus, Enter picks all ticked ones
+        let pick = |menu: &mut Menu, index: usize| {
+            if menu.multi_select {
+                menu.toggle(index);
+                Noop
+            } else if menu.is_enabled(index) {
+                MenuChoice(index)
+            } else {
+                Noop
+            }
+        };
+
+        // Moving the mouse over an entry highlights it, clicking picks it
+        let (mouse_x, mouse_y) = (data.mouse.cx as i32, data.mouse.cy as i32);
+        let hovered = menu.top_left.and_then(|(x, y)| {
+            let index = page.start as i32 + mouse_y - y;
+            if mouse_x >= x && mouse_x < x + menu.width && mouse_y >= y && index < page.end as i32 {
+                Some(index as usize)
+            } else {
+                None
+            }
+        });
+        if hovered != menu.hovered {
+            menu.hovered = hovered;
+            if let Some(index) = hovered.filter(|index| menu.is_enabled(*index)) {
+                menu.cursor = index;
+            }
+        }
+
+        *data.action = match (data.key.as_ref(), hovered) {
+            (Some(k), _) => match (MenuKey::of(k.code), k.code) {
+                (Some(MenuKey::Dismiss), _) => DismissMenu,
+                _ if menu.capture_key => match KeyBinding::from_key(k) {
+                    Some(key) => {
+                        menu.captured_key = Some(key);
+                        MenuChoice(menu.cursor)
+                    }
+                    None => Noop,
+                },
+                (Some(MenuKey::Choose), _) => {
+                    if menu.can_choose() {
+                        MenuChoice(menu.cursor)
                     } else {
-                        let n = c.unwrap().to_ascii_lowercase() as usize - 'a' as usize;
-                        if n < menu.items.len() {
-                            MenuChoice(n)
-                        } else {
-                            Noop
-                        }
+                        Noop
                     }
                 }
+                (Some(MenuKey::Erase), _) if menu.text.is_some() => {
+                    menu.text.as_mut().unwrap().pop();
+                    Noop
+                }
+                (_, Text) if menu.text.is_some() => {
+                    let text = menu.text.as_mut().unwrap();
+                    let room = (menu.width as usize).saturating_sub(text.chars().count() + 3);
+                    text.extend(k.text().chars().filter(|c| !c.is_control()).take(room));
+                    Noop
+                }
+                (Some(MenuKey::Tick), _) if menu.multi_select => {
+                    let cursor = menu.cursor;
+                    pick(menu, cursor)
+                }
+                (Some(MenuKey::Previous), _) => {
+                    menu.move_cursor(-1);
+                    Noop
+                }
+                (Some(MenuKey::Next), _) => {
+                    menu.move_cursor(1);
+                    Noop
+                }
+                (Some(MenuKey::PreviousPage), _) => {
+                    menu.move_cursor(-(MENU_PAGE_SIZE as isize));
+                    Noop
+                }
+                (Some(MenuKey::NextPage), _) => {
+                    menu.move_cursor(MENU_PAGE_SIZE as isize);
+                    Noop
+                }
+                (_, Text) => match k.text().chars().next().and_then(menu_index) {
+                    Some(n) if page.start + n < page.end => pick(menu, page.start + n),
+                    _ => Noop,
+                },
                 _ => Noop,
+            },
+            (None, Some(index)) if data.mouse.lbutton_pressed => pick(menu, index),
+            _ => Noop,
+        };
+
+        *data.key = None;
+    }
+
+    fn start_firing(data: &mut InputSystemData) -> InputAction {
+        let (inventory, _) = (&data.inventory, &data.player).join().next().unwrap();
+
+        // No equipment slots (yet), so the first launcher in the inventory is the one we use
+        let launcher = inventory
+            .stacks
+            .iter()
+            .map(|stack| stack.top())
+            .find(|item| data.launcher.get(*item).is_some());
+        let launcher = match launcher {
+            Some(launcher) => launcher,
+            None => {
+                data.messages
+                    .push("You have nothing to fire with.", colors::WHITE);
+                return Noop;
             }
-        } else {
-            Noop
         };
 
+        let launcher_data = data.launcher.get(launcher).unwrap();
+        let has_ammo = inventory
+            .find_stack(|item| {
+                data.ammo
+                    .get(item)
+                    .map_or(false, |ammo| ammo.kind == launcher_data.ammo)
+            })
+            .is_some();
+        if !has_ammo {
+            data.messages.push(
+                format!(
+                    "You have no ammunition for your {}.",
+                    data.name.get(launcher).unwrap().0
+                ),
+                colors::WHITE,
+            );
+            return Noop;
+        }
+
+        *data.targeting = Some(Targeting {
+            used_item: launcher,
+            kind: TargetingKind::Tile,
+            intent: TargetingIntent::Fire,
+            max_range: Some(launcher_data.range as f32),
+        });
+        data.messages.push(
+            "Left-click a target to fire at, or right-click to cancel.",
+            colors::CYAN,
+        );
+        Noop
+    }
+
+    fn handle_targeting_input(mut data: InputSystemData) {
+        *data.action = InputSystem::pick_target(&mut data);
         *data.key = None;
     }
 
-    //    fn handle_targeting_input(data: &mut InputSystemData) -> InputAction {
-    //        // Cancel targeting on right mouse button and escape
-    //        if data.mouse.rbutton_pressed || data.key.map_or(false, |k| k.code == Escape) {
-    //            *data.targeting = None;
-    //            data.messages.push("Cancelled", colors::WHITE);
-    //            return InputAction::Noop;
-    //        }
-    //
-    //        // We only want to do work if the LMB has been pressed
-    //        if !data.mouse.lbutton_pressed {
-    //            return InputAction::Noop;
-    //        }
-    //
-    //        let mouse_position = Position {
-    //            x: data.mouse.cx as i32,
-    //            y: data.mouse.cy as i32,
-    //        };
-    //        let player_position: &Position = (&data.position, &data.player).join().next().unwrap().0;
-    //
-    //        // Only accept positions in the FOV
-    //        let is_in_fov = {
-    //            let fov_map_mutex = data.fov_map.as_ref().unwrap().clone();
-    //            let fov_map = &*fov_map_mutex.lock().unwrap();
-    //            fov_map.is_in_fov(mouse_position.x, mouse_position.y)
-    //        };
-    //        if !is_in_fov {
-    //            return InputAction::Noop;
-    //        }
-    //
-    //        // Apply max_range restriction, if any
-    //        let on_target = {
-    //            let targeting = data.targeting.as_ref().unwrap();
-    //            if targeting
-    //                .max_range
-    //                .map_or(false, |r| r < player_position.distance_to(&mouse_position))
-    //            {
-    //                return InputAction::Noop;
-    //            }
-    //
-    //            // If we're targeting monsters, ensure there's a live monster under the cursor
-    //            if targeting.kind == TargetingKind::Monster
-    //                && (&data.position, &data.living, &data.ai)
-    //                    .join()
-    //                    .find(|j| j.0 == &mouse_position && j.1.alive)
-    //                    .is_none()
-    //            {
-    //                return Noop;
-    //            }
-    //
-    //            // After all that, if we're still here, then the player clicked something we like
-    //            match data.item.get(targeting.used_item).unwrap() {
-    //                Item::Confuse => cast_confuse,
-    //                Item::Fireball => cast_fireball,
-    //                // TODO maybe somehow ensure in the type system we can never get here unless using a targetable thing
-    //                _ => unreachable!(),
-    //            }
-    //        };
-    //
-    //        let result = on_target(&mouse_position, data);
-    //
-    //        let targeting = data.targeting.as_ref().unwrap();
-    //        let player_action = handle_use_result(targeting.used_item, result, data);
-    //
-    //        // We're done targeting
-    //        *data.targeting = None;
-    //        player_action
-    //    }
+    fn pick_target(data: &mut InputSystemData) -> InputAction {
+        let key = data.key.as_ref();
+        if TargetingKey::Cancel.pressed(&data.mouse, key) {
+            *data.targeting = None;
+            data.messages.push("Cancelled", colors::WHITE);
+            return Noop;
+        }
+
+        // We only want to do work if the target has been picked
+        if !TargetingKey::Pick.pressed(&data.mouse, key) {
+            return Noop;
+        }
+
+        // Clicking on the panel doesn't pick anything
+        let mouse_position = match data
+            .camera
+            .to_world(data.mouse.cx as i32, data.mouse.cy as i32)
+        {
+            Some(position) => position,
+            None => return Noop,
+        };
+        let player_position = (&data.position, &data.player).join().next().unwrap().0;
+
+        // Only accept positions in the FOV
+        let is_in_fov = {
+            let fov_map_mutex = data.fov_map.as_ref().unwrap().clone();
+            let fov_map = &*fov_map_mutex.lock().unwrap();
+            let (width, height) = fov_map.size();
+            mouse_position.x < width
+                && mouse_position.y < height
+                && fov_map.is_in_fov(mouse_position.x, mouse_position.y)
+        };
+        if !is_in_fov {
+            return Noop;
+        }
+
+        let action = {
+            let targeting = data.targeting.as_ref().unwrap();
+
+            // Apply max_range restriction, if any
+            if targeting
+                .max_range
+                .map_or(false, |r| r < player_position.distance_to(&mouse_position))
+            {
+                return Noop;
+            }
+
+            // If we're targeting monsters, ensure there's a live monster under the cursor
+            if targeting.kind == TargetingKind::Monster
+                && (&data.position, &data.living, &data.ai)
+                    .join()
+                    .find(|j| j.0 == &mouse_position && j.1.alive)
+                    .is_none()
+            {
+                return Noop;
+            }
+
+            // After all that, if we're still here, then the player clicked something we like
+            match targeting.intent {
+                TargetingIntent::Fire => FireAt(mouse_position.x, mouse_position.y),
+                TargetingIntent::Throw => {
+                    let (inventory, _) = (&data.inventory, &data.player).join().next().unwrap();
+                    match inventory.index_of(targeting.used_item) {
+                        Some(index) => ThrowAt(index, mouse_position.x, mouse_position.y),
+                        None => Noop,
+                    }
+                }
+                // Targeted scrolls are not wired up to actions yet
+                TargetingIntent::UseItem => Noop,
+            }
+        };
+
+        // We're done targeting
+        *data.targeting = None;
+        action
+    }
 }
 
 impl<'a> System<'a> for InputSystem {
@@ -178,7 +460,7 @@ impl<'a> System<'a> for InputSystem {
         if data.menu.is_some() {
             InputSystem::handle_menu_input(data);
         } else if data.targeting.is_some() {
-            //            *data.action = InputSystem::handle_targeting_input(&mut data);
+            InputSystem::handle_targeting_input(data);
         } else {
             InputSystem::handle_game_input(data);
         }
@@ -0,0 +1,126 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+
+use crate::resources::{
+    input_action::InputAction,
+    keymap::{describe_action, Keymap},
+    menu::{Menu, MenuItem, MenuResult},
+    state::State,
+};
+
+/// Shows the key bindings screen, and changes and saves the keymap when a key is rebound
+pub struct KeymapSystem;
+
+const KEYMAP_MENU_WIDTH: i32 = 50;
+
+#[derive(SystemData)]
+pub struct KeymapSystemData<'a> {
+    input_action: WriteExpect<'a, InputAction>,
+    keymap: Write<'a, Keymap>,
+    menu: WriteExpect<'a, Option<Menu>>,
+    state: Read<'a, State>,
+}
+
+/// An action picked from the key bindings screen
+fn binding_menu(index: usize, name: &'static str, label: String) -> Menu {
+    let items = vec![MenuItem::new("Add a key"), MenuItem::new("Remove its keys")];
+    Menu::new(
+        format!("{}\n", label),
+        KEYMAP_MENU_WIDTH,
+        items,
+        move |selection| {
+            if selection.index == 0 {
+                MenuResult::Open(Menu::key_capture(
+                    format!(
+                        "Press the key to {}, escape to cancel.\n",
+                        name.to_lowercase()
+                    ),
+                    KEYMAP_MENU_WIDTH,
+                    move |selection| match selection.key {
+                        Some(key) => MenuResult::Action(InputAction::BindKey(index, key)),
+                        None => MenuResult::Back,
+                    },
+                ))
+            } else {
+                MenuResult::Action(InputAction::UnbindKeys(index))
+            }
+        },
+    )
+}
+
+fn keymap_menu(keymap: &Keymap, from_main_menu: bool, cursor: usize, error: String) -> Menu {
+    let names: Vec<_> = keymap
+        .bindings
+        .iter()
+        .map(|(action, _)| describe_action(action))
+        .collect();
+    let labels: Vec<_> = keymap
+        .bindings
+        .iter()
+        .zip(&names)
+        .map(|((_, keys), name)| format!("{}: {}", name, Keymap::describe_keys(keys)))
+        .collect();
+    let mut items: Vec<_> = labels.iter().map(MenuItem::new).collect();
+    items.push(MenuItem::new("Reset all keys to their defaults"));
+
+    let mut menu = Menu::new(
+        format!("{}Choose an action to rebind, escape to go back.\n", error),
+        KEYMAP_MENU_WIDTH,
+        items,
+        move |selection| match labels.get(selection.index) {
+            Some(label) => {
+                let index = selection.index;
+                MenuResult::Open(binding_menu(index, names[index], label.clone()))
+            }
+            None => MenuResult::Action(InputAction::ResetKeymap),
+        },
+    );
+    menu.cursor = cursor;
+    // from the main menu, backing out goes back there
+    if from_main_menu {
+        menu.on_dismiss(|_| MenuResult::Action(InputAction::MainMenu))
+    } else {
+        menu
+    }
+}
+
+impl<'a> System<'a> for KeymapSystem {
+    type SystemData = KeymapSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let cursor = match *data.input_action {
+            InputAction::OpenKeymapMenu => 0,
+            InputAction::BindKey(index, key) => {
+                data.keymap.bind(index, key);
+                index
+            }
+            InputAction::UnbindKeys(index) => {
+                data.keymap.unbind(index);
+                index
+            }
+            InputAction::ResetKeymap => {
+                *data.keymap = Keymap::default();
+                data.keymap.bindings.len()
+            }
+            _ => return,
+        };
+
+        // every change is saved right away
+        let error = if *data.input_action == InputAction::OpenKeymapMenu {
+            String::new()
+        } else {
+            match data.keymap.save() {
+                Ok(()) => String::new(),
+                Err(e) => format!(
+                    "Could not save the key bindings to {}: {}\n",
+                    Keymap::path().display(),
+                    e
+                ),
+            }
+        };
+
+        let from_main_menu = *data.state == State::MainMenu;
+        *data.menu = Some(keymap_menu(&data.keymap, from_main_menu, cursor, error));
+        *data.input_action = InputAction::Noop;
+    }
+}
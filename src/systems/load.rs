@@ -1,113 +1,139 @@
-use std::fs::File;
+use std::fmt;
+use std::fs;
 
+use serde::{
+    de::{Error as _, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 use shred_derive::SystemData;
 use specs::{
-    error::NoError,
     prelude::*,
-    saveload::{DeserializeComponents, U64Marker, U64MarkerAllocator},
+    saveload::{U64Marker, U64MarkerAllocator},
 };
 
 use crate::{
-    components::*, resources::map::Map, resources::messages::Messages, systems::save::Synthetic,
+    resources::{
+        identification::Identification, map::Map, messages::Messages, save_error::SaveError,
+        save_slot::SaveSlot,
+    },
+    save_format::{
+        corrupt, migrate, read_save, LoadedStorages, SaveBackend, SaveHeader, SaveReader,
+    },
 };
-use std::io::Read;
 
 #[derive(SystemData)]
 pub struct LoadSystemData<'a> {
     entity: Entities<'a>,
-    components0: (
-        WriteStorage<'a, Collider>,
-        WriteStorage<'a, Inventory>,
-        WriteStorage<'a, Item>,
-        WriteStorage<'a, Living>,
-        WriteStorage<'a, Map>,
-        WriteStorage<'a, Messages>,
-        WriteStorage<'a, Name>,
-        WriteStorage<'a, Player>,
-        WriteStorage<'a, Position>,
-        WriteStorage<'a, PreviousPosition>,
-        WriteStorage<'a, Power>,
-        WriteStorage<'a, Synthetic>,
-        WriteStorage<'a, Velocity>,
-        WriteStorage<'a, Visual>,
-        WriteStorage<'a, Energy>,
-        WriteStorage<'a, Action>,
-    ),
-    components1: (WriteStorage<'a, Ai>,),
+    storages: LoadedStorages<'a>,
 
     allocator: Write<'a, U64MarkerAllocator>,
     marker: WriteStorage<'a, U64Marker>,
 
     map_res: WriteExpect<'a, Map>,
     messages_res: Write<'a, Messages>,
-}
+    identification_res: Write<'a, Identification>,
 
-macro_rules! do_deser {
-    ($de:expr, $data:ident.$field:ident) => {
-        DeserializeComponents::<Combined, _>::deserialize(
-            &mut $data.$field,
-            &$data.entity,
-            &mut $data.marker,
-            &mut $data.allocator,
-            &mut $de,
-        )
-        .unwrap();
-    };
+    slot: Read<'a, SaveSlot>,
+    error: Write<'a, Option<SaveError>>,
 }
 
-pub struct LoadSystem;
+/// Reads the components of a save with the given format version into the world
+struct SavedComponents<'s, 'a> {
+    data: &'s mut LoadSystemData<'a>,
+    version: u32,
+    backend: SaveBackend,
+}
 
-impl<'a> System<'a> for LoadSystem {
-    type SystemData = LoadSystemData<'a>;
+impl<'de, 's, 'a> Visitor<'de> for SavedComponents<'s, 'a> {
+    type Value = ();
 
-    fn run(&mut self, mut data: Self::SystemData) {
-        use ron::de::Deserializer;
-        let mut buf = String::new();
-        let mut file = File::open("savegame").unwrap();
-        file.read_to_string(&mut buf).unwrap();
-        if let Ok(mut de) = Deserializer::from_str(&buf) {
-            do_deser!(de, data.components0);
-            do_deser!(de, data.components1);
-        }
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of component names to components")
+    }
 
-        // Pull in global stuff from the synthetic entity they were saved onto, and clean them up
-        // from the world space
-        for (entity, map, messages) in
-            (&data.entity, &data.components0.4, &data.components0.5).join()
-        {
-            *data.map_res = map.clone();
-            *data.messages_res = messages.clone();
-            data.entity.delete(entity).unwrap();
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let data = self.data;
+        while let Some(name) = map.next_key::<String>()? {
+            // read them whether we want them or not, that's how removed components get skipped
+            let bytes = self.backend.next_entries(&mut map)?;
+            let (name, bytes) =
+                match migrate(self.version, name, bytes, self.backend).map_err(A::Error::custom)? {
+                    Some(migrated) => migrated,
+                    None => continue,
+                };
+            let known = data
+                .storages
+                .read(
+                    &name,
+                    &bytes,
+                    self.backend,
+                    &mut data.marker,
+                    &mut data.allocator,
+                    &data.entity,
+                )
+                .map_err(|e| A::Error::custom(format!("{}: {}", name, e)))?;
+            if !known {
+                return Err(A::Error::custom(format!("unknown component {}", name)));
+            }
         }
+        Ok(())
     }
 }
 
-// DeserializeComponents needs all this for whatever reason
+/// Reads a whole save, header first
+struct Game<'s, 'a>(&'s mut LoadSystemData<'a>);
 
-#[derive(Debug)]
-enum Combined {
-    Ron(ron::ser::Error),
-}
+impl<'s, 'a> SaveReader for Game<'s, 'a> {
+    type Output = ();
 
-// Implementing the required `Display`-trait, by matching the `Combined` enum, allowing different error types to be displayed.
-impl std::fmt::Display for Combined {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
-            Combined::Ron(ref e) => write!(f, "{}", e),
-        }
+    fn read<'de, D>(self, de: &mut D, backend: SaveBackend) -> Result<(), SaveError>
+    where
+        for<'b> &'b mut D: Deserializer<'de>,
+    {
+        let header = SaveHeader::deserialize(&mut *de).map_err(corrupt)?;
+        header.check()?;
+        let version = header.format_version;
+        de.deserialize_map(SavedComponents {
+            data: self.0,
+            version,
+            backend,
+        })
+        .map_err(corrupt)
     }
 }
 
-// This returns the `ron::ser::Error` in form of the `Combined` enum, which can then be matched and displayed accordingly.
-impl From<ron::ser::Error> for Combined {
-    fn from(x: ron::ser::Error) -> Self {
-        Combined::Ron(x)
+fn load(data: &mut LoadSystemData) -> Result<(), SaveError> {
+    read_save(&fs::read(data.slot.path())?, Game(&mut *data))?;
+
+    // Pull in global stuff from the synthetic entity they were saved onto, and clean them up
+    // from the world space
+    let mut found = false;
+    for (entity, map, messages, identification) in (
+        &data.entity,
+        &data.storages.map,
+        &data.storages.messages,
+        &data.storages.identification,
+    )
+        .join()
+    {
+        *data.map_res = map.clone();
+        *data.messages_res = messages.clone();
+        *data.identification_res = identification.clone();
+        data.entity.delete(entity).unwrap();
+        found = true;
+    }
+    if !found {
+        return Err(SaveError::Corrupt("no map in the saved game".to_string()));
     }
+    Ok(())
 }
 
-// This cannot be called.
-impl From<NoError> for Combined {
-    fn from(e: NoError) -> Self {
-        match e {}
+pub struct LoadSystem;
+
+impl<'a> System<'a> for LoadSystem {
+    type SystemData = LoadSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        *data.error = load(&mut data).err();
     }
 }
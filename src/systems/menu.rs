@@ -1,27 +1,275 @@
+use std::sync::Arc;
+
 use shred_derive::SystemData;
 use specs::prelude::*;
+use tcod::colors;
 
 use crate::resources::ui::INVENTORY_WIDTH;
 use crate::{
     components::*,
     resources::{
+        identification::Identification,
         input_action::InputAction,
-        menu::{Menu, MenuKind},
+        keymap::KeymapError,
+        menu::{Menu, MenuItem, MenuResult},
+        messages::Messages,
+        save_error::SaveError,
+        save_slot::{SaveSlot, NUM_SAVE_SLOTS},
         state::State,
+        targeting::{Targeting, TargetingIntent, TargetingKind},
     },
 };
 
 pub struct MenuSystem;
 
+const THROW_RANGE: f32 = 8.0;
+const MAIN_MENU_WIDTH: i32 = 24;
+const SLOT_MENU_WIDTH: i32 = 50;
+
+fn main_menu(header: String) -> Menu {
+    let any_saves = (0..NUM_SAVE_SLOTS).any(|slot| !SaveSlot(slot).is_empty());
+    let items = vec![
+        MenuItem::new("Play a new game"),
+        if any_saves {
+            MenuItem::new("Load a saved game")
+        } else {
+            MenuItem::disabled("Load a saved game")
+        },
+        MenuItem::new("Key bindings"),
+        MenuItem::new("Quit"),
+    ];
+    Menu::new(
+        header,
+        MAIN_MENU_WIDTH,
+        items,
+        |selection| match selection.index {
+            0 => MenuResult::Open(name_menu()),
+            1 => MenuResult::Replace(load_menu(String::new())),
+            2 => MenuResult::Action(InputAction::OpenKeymapMenu),
+            _ => MenuResult::Action(InputAction::Exit),
+        },
+    )
+}
+
+fn name_menu() -> Menu {
+    Menu::text_entry(
+        "What is your name?\n".to_string(),
+        MAIN_MENU_WIDTH,
+        "",
+        |selection| {
+            let name = selection.text.trim();
+            let name = if name.is_empty() { "player" } else { name };
+            match SaveSlot::for_new_game() {
+                Some(slot) => MenuResult::Action(InputAction::NewGame(name.to_string(), slot.0)),
+                None => MenuResult::Replace(replace_menu(name.to_string())),
+            }
+        },
+    )
+}
+
+/// When every slot is taken, one of the saved games has to make room for the new one
+fn replace_menu(name: String) -> Menu {
+    slot_menu(
+        "All save slots are full. Choose a game to replace, escape to go back.\n".to_string(),
+        true,
+        move |slot| {
+            let name = name.clone();
+            MenuResult::Open(confirm_menu(
+                format!(
+                    "{}\nThis game will be deleted. Are you sure?\n",
+                    SaveSlot(slot).describe()
+                ),
+                move || MenuResult::Action(InputAction::NewGame(name.clone(), slot)),
+                || MenuResult::Back,
+            ))
+        },
+    )
+}
+
+/// Asks before doing something that can't be undone. Escape is the same as no.
+fn confirm_menu<F, G>(header: String, on_yes: F, on_no: G) -> Menu
+where
+    F: Fn() -> MenuResult + Send + Sync + 'static,
+    G: Fn() -> MenuResult + Send + Sync + 'static,
+{
+    let on_no = Arc::new(on_no);
+    let on_dismiss = on_no.clone();
+    let items = vec![MenuItem::new("Yes"), MenuItem::new("No")];
+    let mut menu = Menu::new(header, SLOT_MENU_WIDTH, items, move |selection| {
+        if selection.index == 0 {
+            on_yes()
+        } else {
+            on_no()
+        }
+    })
+    .on_dismiss(move |_| on_dismiss());
+    // so a stray Enter doesn't do it
+    menu.cursor = 1;
+    menu
+}
+
+/// Lists all save slots and what's in them. Empty ones are disabled unless `allow_empty`.
+fn slot_menu<F>(header: String, allow_empty: bool, on_choose: F) -> Menu
+where
+    F: Fn(usize) -> MenuResult + Send + Sync + 'static,
+{
+    let items = (0..NUM_SAVE_SLOTS)
+        .map(SaveSlot)
+        .map(|slot| {
+            if !allow_empty && slot.is_empty() {
+                MenuItem::disabled(slot.describe())
+            } else {
+                MenuItem::new(slot.describe())
+            }
+        })
+        .collect();
+    Menu::new(header, SLOT_MENU_WIDTH, items, move |selection| {
+        on_choose(selection.index)
+    })
+}
+
+/// Saving to another slot moves the game there
+fn save_menu(current: SaveSlot) -> Menu {
+    slot_menu(
+        "Choose a slot to save to and quit, escape to keep playing.\n".to_string(),
+        true,
+        move |slot| {
+            let save = move || MenuResult::Action(InputAction::SaveGame(slot));
+            if slot == current.0 || SaveSlot(slot).is_empty() {
+                save()
+            } else {
+                MenuResult::Open(confirm_menu(
+                    format!(
+                        "{}\nThis game will be deleted. Are you sure?\n",
+                        SaveSlot(slot).describe()
+                    ),
+                    save,
+                    || MenuResult::Back,
+                ))
+            }
+        },
+    )
+}
+
+/// Backing out of loading a game goes back to the main menu
+fn load_menu(error: String) -> Menu {
+    slot_menu(
+        format!("{}Choose a saved game, escape to go back.\n", error),
+        false,
+        |slot| MenuResult::Replace(saved_game_menu(slot)),
+    )
+    .on_dismiss(|_| MenuResult::Replace(main_menu(String::new())))
+}
+
+/// What to do with the saved game in a slot
+fn saved_game_menu(slot: usize) -> Menu {
+    let items = vec![
+        MenuItem::new("Load this game"),
+        MenuItem::new("Delete this game"),
+    ];
+    Menu::new(
+        format!("{}\n", SaveSlot(slot).describe()),
+        SLOT_MENU_WIDTH,
+        items,
+        move |selection| {
+            if selection.index == 0 {
+                MenuResult::Action(InputAction::LoadGame(slot))
+            } else {
+                MenuResult::Replace(confirm_menu(
+                    format!(
+                        "{}\nDelete this game for good?\n",
+                        SaveSlot(slot).describe()
+                    ),
+                    move || {
+                        let error = match SaveSlot(slot).delete() {
+                            Ok(()) => String::new(),
+                            Err(e) => format!("{}\n", e),
+                        };
+                        MenuResult::Replace(load_menu(error))
+                    },
+                    move || MenuResult::Replace(saved_game_menu(slot)),
+                ))
+            }
+        },
+    )
+    .on_dismiss(|_| MenuResult::Replace(load_menu(String::new())))
+}
+
+/// An entry for each stack in the inventory, and the item on top of each
+fn inventory_options(inventory: &Inventory, data: &MenuSystemData) -> (Vec<MenuItem>, Vec<Entity>) {
+    if inventory.is_empty() {
+        return (vec![MenuItem::disabled("Inventory is empty.")], vec![]);
+    }
+    inventory
+        .stacks
+        .iter()
+        .map(|stack| {
+            let item = stack.top();
+            let name = data
+                .identification
+                .name_of(data.item.get(item), data.name.get(item).unwrap());
+            let label = if stack.count() > 1 {
+                format!("{} (x{})", name, stack.count())
+            } else {
+                name
+            };
+            (MenuItem::new(label), item)
+        })
+        .unzip()
+}
+
+fn throw(item: Entity) -> MenuResult {
+    MenuResult::Target(
+        Targeting {
+            used_item: item,
+            kind: TargetingKind::Tile,
+            intent: TargetingIntent::Throw,
+            max_range: Some(THROW_RANGE),
+        },
+        "Left-click a target tile to throw at, or right-click to cancel.",
+    )
+}
+
+/// What to do with an item picked from the inventory
+fn item_menu(index: usize, item: Entity, label: &str) -> Menu {
+    let items = vec![
+        MenuItem::new("Use"),
+        MenuItem::new("Drop"),
+        MenuItem::new("Throw"),
+    ];
+    Menu::new(
+        format!("{}\n", label),
+        INVENTORY_WIDTH,
+        items,
+        move |selection| match selection.index {
+            0 => MenuResult::Action(InputAction::UseFromInventory(index)),
+            1 => MenuResult::Action(InputAction::Drop(vec![index])),
+            _ => throw(item),
+        },
+    )
+}
+
+fn player_inventory<'b>(data: &'b MenuSystemData) -> &'b Inventory {
+    (&data.inventory, &data.player).join().next().unwrap().0
+}
+
 #[derive(SystemData)]
 pub struct MenuSystemData<'a> {
     inventory: ReadStorage<'a, Inventory>,
     player: ReadStorage<'a, Player>,
     name: ReadStorage<'a, Name>,
+    item: ReadStorage<'a, Item>,
+    living: ReadStorage<'a, Living>,
 
     input_action: WriteExpect<'a, InputAction>,
     menu: WriteExpect<'a, Option<Menu>>,
     state: Write<'a, State>,
+    identification: Read<'a, Identification>,
+    messages: Write<'a, Messages>,
+    targeting: WriteExpect<'a, Option<Targeting>>,
+    save_error: Write<'a, Option<SaveError>>,
+    keymap_error: Write<'a, Option<KeymapError>>,
+    slot: Read<'a, SaveSlot>,
 }
 
 impl<'a> System<'a> for MenuSystem {
@@ -32,92 +280,119 @@ impl<'a> System<'a> for MenuSystem {
 
         match input_action {
             InputAction::MainMenu => {
-                *data.menu = Some(Menu {
-                    items: vec![
-                        "Play a new game".to_string(),
-                        "Continue last game".to_string(),
-                        "Quit".to_string(),
-                    ],
-                    header: "".to_string(),
-                    width: 24,
-                    kind: MenuKind::Main,
-                });
+                // the last save or load that went wrong, and any trouble with the key bindings
+                let header = data
+                    .save_error
+                    .take()
+                    .map_or_else(String::new, |e| format!("{}\n", e))
+                    + &data
+                        .keymap_error
+                        .take()
+                        .map_or_else(String::new, |e| format!("{}\n", e));
+                *data.menu = Some(main_menu(header));
                 *data.state = State::MainMenu;
             }
 
+            InputAction::OpenSaveMenu => {
+                // the dead don't get to save
+                if !(&data.living, &data.player)
+                    .join()
+                    .any(|(living, _)| living.alive)
+                {
+                    *data.input_action = InputAction::MainMenu;
+                    return;
+                }
+                *data.menu = Some(save_menu(*data.slot));
+            }
+
             InputAction::OpenInventoryMenu => {
-                let inventory = (&data.inventory, &data.player).join().next().unwrap().0;
-                let options: Vec<String> = if inventory.0.len() == 0 {
-                    vec!["Inventory is empty.".to_string()]
-                } else {
-                    inventory
-                        .0
-                        .iter()
-                        .map(|item| data.name.get(*item).unwrap().0.clone())
-                        .collect()
-                };
-
-                *data.menu = Some(Menu {
-                    header: "Press the key next to an item to use it, escape to cancel.\n"
-                        .to_string(),
-                    width: INVENTORY_WIDTH,
-                    items: options,
-                    kind: MenuKind::Inventory,
-                })
+                let (options, items) = inventory_options(player_inventory(&data), &data);
+                let labels: Vec<_> = options.iter().map(|o| o.label.clone()).collect();
+
+                *data.menu = Some(Menu::new(
+                    "Press the key next to an item to choose it, escape to cancel.\n".to_string(),
+                    INVENTORY_WIDTH,
+                    options,
+                    move |selection| {
+                        let index = selection.index;
+                        MenuResult::Open(item_menu(index, items[index], &labels[index]))
+                    },
+                ))
             }
 
             InputAction::OpenDropMenu => {
-                let inventory = (&data.inventory, &data.player).join().next().unwrap().0;
-                let options: Vec<String> = if inventory.0.len() == 0 {
-                    vec!["Inventory is empty.".to_string()]
-                } else {
-                    inventory
-                        .0
-                        .iter()
-                        .map(|item| data.name.get(*item).unwrap().0.clone())
-                        .collect()
-                };
-
-                *data.menu = Some(Menu {
-                    header: "Press the key next to an item to drop it, escape to cancel.\n"
-                        .to_string(),
-                    width: INVENTORY_WIDTH,
-                    items: options,
-                    kind: MenuKind::Drop,
-                });
+                let (options, _) = inventory_options(player_inventory(&data), &data);
+
+                *data.menu = Some(
+                    Menu::new(
+                        "Press the keys next to the items to drop, then enter. Escape to cancel.\n"
+                            .to_string(),
+                        INVENTORY_WIDTH,
+                        options,
+                        |selection| {
+                            MenuResult::Action(InputAction::Drop(selection.selected.clone()))
+                        },
+                    )
+                    .multi_select(),
+                );
+            }
+
+            InputAction::OpenThrowMenu => {
+                let (options, items) = inventory_options(player_inventory(&data), &data);
+
+                *data.menu = Some(Menu::new(
+                    "Press the key next to an item to throw it, escape to cancel.\n".to_string(),
+                    INVENTORY_WIDTH,
+                    options,
+                    move |selection| throw(items[selection.index]),
+                ));
             }
 
             InputAction::MenuChoice(choice) => {
-                if let Some(kind) = &data.menu.as_ref().map(|m| m.kind) {
-                    match kind {
-                        MenuKind::Main => {
-                            *data.input_action = match choice {
-                                0 => InputAction::NewGame,
-                                1 => InputAction::LoadGame,
-                                2 => InputAction::Exit,
-                                _ => unreachable!(),
-                            };
-                            *data.menu = None;
-                        }
-
-                        MenuKind::Drop => {
-                            *data.input_action = InputAction::Drop(choice);
-                            *data.menu = None;
-                        }
-
-                        MenuKind::Inventory => {
-                            *data.input_action = InputAction::UseFromInventory(choice);
-                            *data.menu = None;
-                        }
-                    }
+                if let Some(menu) = data.menu.as_mut() {
+                    menu.cursor = choice;
+                    let result = menu.choose();
+                    apply(result, &mut data);
                 }
             }
 
             InputAction::DismissMenu => {
-                *data.menu = None;
+                if let Some(result) = data.menu.as_ref().map(|menu| menu.dismiss()) {
+                    apply(result, &mut data);
+                }
             }
 
             _ => (),
         }
     }
 }
+
+fn apply(result: MenuResult, data: &mut MenuSystemData) {
+    *data.input_action = InputAction::Noop;
+    match result {
+        MenuResult::Action(action) => {
+            *data.menu = None;
+            *data.input_action = action;
+        }
+        MenuResult::Open(mut menu) => {
+            menu.parent = data.menu.take().map(Box::new);
+            *data.menu = Some(menu);
+        }
+        MenuResult::Replace(mut menu) => {
+            menu.parent = data.menu.take().and_then(|current| current.parent);
+            *data.menu = Some(menu);
+        }
+        MenuResult::Back => {
+            *data.menu = data
+                .menu
+                .take()
+                .and_then(|current| current.parent)
+                .map(|parent| *parent);
+        }
+        MenuResult::Target(targeting, prompt) => {
+            *data.menu = None;
+            *data.targeting = Some(targeting);
+            data.messages.push(prompt, colors::CYAN);
+        }
+    }
+}
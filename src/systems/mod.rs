@@ -1,9 +1,14 @@
 mod ai;
 mod collision;
+mod door;
 mod drop;
+mod encumbrance;
+mod fire;
 mod fog_of_war;
 pub mod fov;
+mod help;
 mod input;
+mod keymap;
 mod load;
 mod location_history;
 mod menu;
@@ -12,18 +17,28 @@ mod move_and_melee;
 mod movement;
 mod pick_up;
 mod player_death;
-mod render;
+mod projectile;
+pub mod render;
 pub mod save;
+mod search;
 mod skip;
+mod terrain;
+mod throw;
 mod time;
+mod trap;
 mod use_item;
 
 pub use ai::AISystem;
 pub use collision::CollisionSystem;
+pub use door::DoorSystem;
 pub use drop::DropSystem;
+pub use encumbrance::EncumbranceSystem;
+pub use fire::FireSystem;
 pub use fog_of_war::FogOfWarSystem;
 pub use fov::FovSystem;
+pub use help::HelpSystem;
 pub use input::InputSystem;
+pub use keymap::KeymapSystem;
 pub use load::LoadSystem;
 pub use location_history::LocationHistorySystem;
 pub use menu::MenuSystem;
@@ -34,6 +49,10 @@ pub use pick_up::PickUpSystem;
 pub use player_death::PlayerDeathSystem;
 pub use render::RenderSystem;
 pub use save::{SavePrepSystem, SaveSystem};
+pub use search::SearchSystem;
 pub use skip::SkipSystem;
+pub use terrain::TerrainSystem;
+pub use throw::ThrowSystem;
 pub use time::TimeSystem;
+pub use trap::TrapSystem;
 pub use use_item::UseItemSystem;
@@ -12,7 +12,7 @@ pub struct MonsterDeathSystem;
 #[derive(SystemData)]
 pub struct MonsterDeathSystemData<'a> {
     living: WriteStorage<'a, Living>,
-    player: ReadStorage<'a, Player>,
+    player: WriteStorage<'a, Player>,
     name: WriteStorage<'a, Name>,
 
     visual: WriteStorage<'a, Visual>,
@@ -35,6 +35,7 @@ impl<'a> System<'a> for MonsterDeathSystem {
             return;
         }
 
+        let mut killed_by_player = false;
         if let Some((mut living, mut name, _, mut visual, entity)) = (
             &mut data.living,
             &mut data.name,
@@ -52,6 +53,10 @@ impl<'a> System<'a> for MonsterDeathSystem {
 
             // You are now dead
             living.alive = false;
+            killed_by_player = living
+                .last_damage
+                .as_ref()
+                .map_or(false, |damage| damage.by_player);
 
             // Horrible to behold
             visual.char = '%';
@@ -64,5 +69,11 @@ impl<'a> System<'a> for MonsterDeathSystem {
             data.energy.remove(entity); // Rest shall grant you no reprieve
             name.0 = format!("remains of {}", name.0); // Even your name shall be forgotten
         }
+
+        if killed_by_player {
+            for player in (&mut data.player).join() {
+                player.kills += 1;
+            }
+        }
     }
 }
@@ -1,10 +1,13 @@
+use std::sync::{Arc, Mutex};
+
 use shred_derive::SystemData;
 use specs::prelude::*;
-use tcod::colors;
+use tcod::{colors, map::Map as FovMap};
 
 use crate::{
     components::*,
-    resources::{messages::Messages, state::State},
+    resources::{map::Map, messages::Messages, state::State},
+    systems::fov::update_fov_tile,
 };
 
 pub struct MoveAndMeleeSystem;
@@ -19,8 +22,13 @@ pub struct MoveAndMeleeSystemData<'a> {
     action: WriteStorage<'a, Action>,
     energy: WriteStorage<'a, Energy>,
     velocity: WriteStorage<'a, Velocity>,
+    burden: ReadStorage<'a, Burden>,
+    inventory: WriteStorage<'a, Inventory>,
+    item: ReadStorage<'a, Item>,
 
     entity: Entities<'a>,
+    map: Option<WriteExpect<'a, Map>>,
+    fov_map: Option<ReadExpect<'a, Arc<Mutex<FovMap>>>>,
     state: WriteExpect<'a, State>,
     messages: Write<'a, Messages>,
 }
@@ -33,17 +41,28 @@ impl<'a> System<'a> for MoveAndMeleeSystem {
             return;
         }
 
-        for (attacker_pos, attacker_velocity, action, energy, attacker_name, attack_power) in (
+        for (
+            attacker,
+            attacker_pos,
+            attacker_velocity,
+            action,
+            energy,
+            attacker_name,
+            attack_power,
+            burden,
+        ) in (
+            &data.entity,
             &data.position,
             &mut data.velocity,
             &mut data.action,
             &mut data.energy,
             &data.name,
             &data.power,
+            (&data.burden).maybe(),
         )
             .join()
         {
-            let energy_cost = action.energy_cost();
+            let energy_cost = action.energy_cost_with(burden);
             match action {
                 Action::MoveOrMelee {
                     velocity,
@@ -51,6 +70,52 @@ impl<'a> System<'a> for MoveAndMeleeSystem {
                     attack_player,
                 } => {
                     let candidate = &*attacker_pos + &velocity;
+
+                    // Bumping into a closed door opens it, if it's locked that needs a key
+                    let map = data.map.as_mut().unwrap();
+                    if map[&candidate].is_closed_door() {
+                        let is_player = data.player.get(attacker).is_some();
+                        // a locked door without a key doesn't cost anything to bump into
+                        let key = if map[&candidate].as_door().unwrap().locked {
+                            let key = data.inventory.get(attacker).and_then(|inventory| {
+                                inventory.find_stack(|item| data.item.get(item) == Some(&Item::Key))
+                            });
+                            if key.is_none() {
+                                if is_player {
+                                    data.messages.push("The door is locked.", colors::WHITE);
+                                }
+                                continue;
+                            }
+                            key
+                        } else {
+                            None
+                        };
+                        if !energy.consume(energy_cost) {
+                            continue;
+                        }
+                        if let Some(index) = key {
+                            let key = data
+                                .inventory
+                                .get_mut(attacker)
+                                .unwrap()
+                                .take(index)
+                                .unwrap();
+                            data.entity.delete(key).unwrap();
+                            if is_player {
+                                data.messages
+                                    .push("You unlock the door.", colors::LIGHT_GREEN);
+                            }
+                        }
+                        map[&candidate].open_door();
+                        let fov_map_mutex = data.fov_map.as_ref().unwrap().clone();
+                        let fov_map = &mut *fov_map_mutex.lock().unwrap();
+                        update_fov_tile(fov_map, &candidate, &map[&candidate]);
+                        if is_player {
+                            data.messages.push("You open the door.", colors::WHITE);
+                        }
+                        continue;
+                    }
+
                     if let Some((_, target_living, target_name, target_entity)) =
                         (&data.position, &mut data.living, &data.name, &data.entity)
                             .join()
@@ -76,7 +141,11 @@ impl<'a> System<'a> for MoveAndMeleeSystem {
                                     ),
                                     colors::WHITE,
                                 );
-                                target_living.hp -= damage;
+                                target_living.take_damage(
+                                    damage,
+                                    format!("the {}", attacker_name.0),
+                                    data.player.get(attacker).is_some(),
+                                );
                             } else {
                                 data.messages.push(
                                     format!(
@@ -89,7 +158,9 @@ impl<'a> System<'a> for MoveAndMeleeSystem {
                             return;
                         }
                     } else {
-                        if energy.consume(energy_cost) {
+                        // Wading through water or climbing over rubble takes longer
+                        let movement_cost = map[&candidate].kind.movement_cost();
+                        if energy.consume(energy_cost * movement_cost) {
                             *attacker_velocity = velocity.clone();
                             return;
                         }
@@ -2,7 +2,10 @@ use shred_derive::SystemData;
 use specs::prelude::*;
 use tcod::colors;
 
-use crate::{components::*, resources::messages::Messages};
+use crate::{
+    components::*,
+    resources::{identification::Identification, messages::Messages},
+};
 
 pub struct PickUpSystem;
 
@@ -18,6 +21,7 @@ pub struct PickUpSystemData<'a> {
     item: ReadStorage<'a, Item>,
 
     messages: Write<'a, Messages>,
+    identification: Read<'a, Identification>,
 }
 
 impl<'a> System<'a> for PickUpSystem {
@@ -38,22 +42,45 @@ impl<'a> System<'a> for PickUpSystem {
                 continue;
             }
             let position = position.unwrap();
-            if let Some((item, name, _, _)) = (&data.entity, &data.name, &data.position, &data.item)
-                .join()
-                .find(|j| j.2 == position)
+            if let Some((_, name, _, item_kind)) =
+                (&data.entity, &data.name, &data.position, &data.item)
+                    .join()
+                    .find(|j| j.2 == position)
             {
-                if inventory.0.len() >= 26 {
+                // Identical items go onto the same stack, so they only take up a single slot
+                let stack = inventory.find_stack(|other| {
+                    data.item.get(other) == Some(item_kind) && data.name.get(other) == Some(name)
+                });
+                let display_name = data.identification.name_of(Some(item_kind), name);
+                if stack.is_none() && inventory.is_full() {
                     // Note, if monsters ever learn to pick things up, this needs to change.
                     // Ideally into some perception system.
                     data.messages.push(
-                        format!("Your inventory is full, cannot pick up {}.", name.0),
+                        format!("Your inventory is full, cannot pick up {}.", display_name),
                         colors::RED,
                     );
                 } else if energy.consume(action.energy_cost()) {
-                    data.position.remove(item);
-                    data.messages
-                        .push(format!("You picked up a {}!", name.0), colors::GREEN);
-                    inventory.0.push(item);
+                    // Pick up the whole pile of identical items (like a bundle of arrows) at once
+                    let pile: Vec<Entity> = (&data.entity, &data.name, &data.position, &data.item)
+                        .join()
+                        .filter(|j| j.2 == position && j.3 == item_kind && j.1 == name)
+                        .map(|j| j.0)
+                        .collect();
+                    if pile.len() > 1 {
+                        data.messages.push(
+                            format!("You picked up {} (x{})!", display_name, pile.len()),
+                            colors::GREEN,
+                        );
+                    } else {
+                        data.messages
+                            .push(format!("You picked up a {}!", display_name), colors::GREEN);
+                    }
+                    let mut stack = stack;
+                    for item in pile {
+                        data.position.remove(item);
+                        inventory.push(item, stack);
+                        stack = stack.or_else(|| Some(inventory.len() - 1));
+                    }
                 }
             }
         }
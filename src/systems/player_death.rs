@@ -4,7 +4,12 @@ use tcod::colors;
 
 use crate::{
     components::*,
-    resources::{messages::Messages, state::State},
+    resources::{
+        messages::Messages,
+        save_error::SaveError,
+        save_slot::{ExplorerMode, SaveSlot},
+        state::State,
+    },
 };
 
 pub struct PlayerDeathSystem;
@@ -15,8 +20,10 @@ pub struct PlayerDeathSystemData<'a> {
     player: ReadStorage<'a, Player>,
     visual: WriteStorage<'a, Visual>,
 
-    state: Read<'a, State>,
+    state: Write<'a, State>,
     messages: Write<'a, Messages>,
+    slot: Read<'a, SaveSlot>,
+    explorer_mode: Read<'a, ExplorerMode>,
 }
 
 impl<'a> System<'a> for PlayerDeathSystem {
@@ -34,10 +41,24 @@ impl<'a> System<'a> for PlayerDeathSystem {
             // the game ended!
             data.messages.push("You died!", colors::RED);
             living.alive = false;
+            *data.state = State::GameOver;
 
             // for added effect, transform the player into a corpse!
             visual.char = '%';
             visual.color = colors::DARK_RED;
+
+            // no coming back from this, unless we're just exploring
+            if data.explorer_mode.0 {
+                data.messages.push(
+                    "Explorer mode: your saved game is kept.",
+                    colors::LIGHT_GREY,
+                );
+            } else {
+                match data.slot.delete() {
+                    Ok(()) | Err(SaveError::NoSave) => (),
+                    Err(e) => data.messages.push(e.to_string(), colors::RED),
+                }
+            }
         }
     }
 }
@@ -0,0 +1,47 @@
+use specs::Entity;
+use tcod::line::Line;
+
+use crate::{components::Position, resources::map::Map};
+
+/// Where a thrown or fired object ended up
+pub struct Flight {
+    pub landing: Position,
+    /// The first blocking entity in the way, if any
+    pub hit: Option<Entity>,
+    pub hit_wall: bool,
+}
+
+/// Follow a straight line from `origin` to `target` until it runs into a wall or one of the
+/// `blockers`.
+pub fn fly(
+    map: &Map,
+    origin: &Position,
+    target: &Position,
+    blockers: &[(Entity, Position)],
+) -> Flight {
+    let mut landing = origin.clone();
+    for (x, y) in Line::new((origin.x, origin.y), (target.x, target.y)) {
+        let step = Position { x, y };
+        if map[&step].blocked() {
+            return Flight {
+                landing,
+                hit: None,
+                hit_wall: true,
+            };
+        }
+        landing = step;
+        if let Some((entity, _)) = blockers.iter().find(|(_, pos)| pos == &landing) {
+            return Flight {
+                landing,
+                hit: Some(*entity),
+                hit_wall: false,
+            };
+        }
+    }
+
+    Flight {
+        landing,
+        hit: None,
+        hit_wall: false,
+    }
+}
@@ -7,12 +7,18 @@ use tcod::{colors::*, console::*, input::Mouse, map::Map as FovMap};
 use crate::{
     components::*,
     resources::{
-        map::{Map, MAP_HEIGHT, MAP_WIDTH},
-        menu::Menu,
+        camera::Camera,
+        identification::Identification,
+        map::{Map, TileKind},
+        menu::{menu_letter, Menu},
         messages::Messages,
         state::State,
-        ui::{UIConsoles, UIState, BAR_WIDTH, PANEL_HEIGHT, PANEL_Y, SCREEN_HEIGHT, SCREEN_WIDTH},
+        ui::{
+            UIConsoles, UIState, BAR_WIDTH, PANEL_HEIGHT, PANEL_Y, SCREEN_HEIGHT, SCREEN_WIDTH,
+            VIEW_HEIGHT, VIEW_WIDTH,
+        },
     },
+    rexpaint,
 };
 
 const COLOR_DARK_WALL: Color = Color { r: 0, g: 0, b: 100 };
@@ -32,6 +38,32 @@ const COLOR_LIGHT_GROUND: Color = Color {
     b: 50,
 };
 
+const COLOR_DOOR: Color = Color {
+    r: 160,
+    g: 100,
+    b: 40,
+};
+const COLOR_SHALLOW_WATER: Color = Color {
+    r: 40,
+    g: 100,
+    b: 200,
+};
+const COLOR_DEEP_WATER: Color = Color {
+    r: 0,
+    g: 30,
+    b: 160,
+};
+const COLOR_LAVA: Color = Color {
+    r: 200,
+    g: 60,
+    b: 0,
+};
+const COLOR_RUBBLE: Color = Color {
+    r: 120,
+    g: 110,
+    b: 100,
+};
+
 const MSG_X: i32 = BAR_WIDTH + 2;
 const MSG_WIDTH: i32 = SCREEN_WIDTH - BAR_WIDTH - 2;
 const MSG_HEIGHT: usize = PANEL_HEIGHT as usize - 1;
@@ -40,46 +72,80 @@ pub struct RenderSystem;
 
 #[derive(SystemData)]
 pub struct RenderSystemData<'a> {
+    burden: ReadStorage<'a, Burden>,
     collider: ReadStorage<'a, Collider>,
+    item: ReadStorage<'a, Item>,
     living: ReadStorage<'a, Living>,
     name: ReadStorage<'a, Name>,
     player: ReadStorage<'a, Player>,
     position: ReadStorage<'a, Position>,
+    trap: ReadStorage<'a, Trap>,
     visual: ReadStorage<'a, Visual>,
 
     entities: Entities<'a>,
 
+    camera: Write<'a, Camera>,
     fov_map: Option<ReadExpect<'a, Arc<Mutex<FovMap>>>>,
+    identification: Read<'a, Identification>,
     map: Option<ReadExpect<'a, Map>>,
     messages: Read<'a, Messages>,
     mouse: ReadExpect<'a, Mouse>,
     ui: WriteExpect<'a, UIState>,
-    menu: ReadExpect<'a, Option<Menu>>,
+    menu: WriteExpect<'a, Option<Menu>>,
     state: ReadExpect<'a, State>,
 }
 
-fn draw_object(offscreen: &mut Offscreen, position: &Position, visual: &Visual) {
-    offscreen.set_default_foreground(visual.color);
-    offscreen.put_char(position.x, position.y, visual.char, BackgroundFlag::None);
+fn draw_object(offscreen: &mut Offscreen, camera: &Camera, position: &Position, visual: &Visual) {
+    if let Some((x, y)) = camera.to_screen(position) {
+        offscreen.set_default_foreground(visual.color);
+        offscreen.put_char(x, y, visual.char, BackgroundFlag::None);
+    }
 }
 
-fn draw_fov(offscreen: &mut Offscreen, map: &Map, fov_map: &FovMap) {
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
-            if !map.tiles[x as usize][y as usize].explored {
+/// Background color, and optionally a glyph with its color, for a tile
+pub fn tile_appearance(kind: &TileKind, visible: bool) -> (Color, Option<(char, Color)>) {
+    let ground = if visible {
+        COLOR_LIGHT_GROUND
+    } else {
+        COLOR_DARK_GROUND
+    };
+    let wall = if visible {
+        COLOR_LIGHT_WALL
+    } else {
+        COLOR_DARK_WALL
+    };
+    let dim = |color: Color| if visible { color } else { color * 0.5 };
+    match kind {
+        TileKind::Floor => (ground, None),
+        TileKind::Wall => (wall, None),
+        TileKind::Door(door) if door.open => (ground, Some(('\'', dim(COLOR_DOOR)))),
+        TileKind::Door(_) => (wall, Some(('+', dim(COLOR_DOOR)))),
+        TileKind::ShallowWater => (dim(COLOR_SHALLOW_WATER), Some(('~', dim(LIGHT_BLUE)))),
+        TileKind::DeepWater => (dim(COLOR_DEEP_WATER), Some(('~', dim(LIGHT_BLUE)))),
+        TileKind::Lava => (dim(COLOR_LAVA), Some(('~', dim(YELLOW)))),
+        TileKind::Chasm => (BLACK, Some((':', dim(DARKER_GREY)))),
+        TileKind::Rubble => (ground, Some((':', dim(COLOR_RUBBLE)))),
+    }
+}
+
+fn draw_fov(offscreen: &mut Offscreen, camera: &Camera, map: &Map, fov_map: &FovMap) {
+    for screen_y in 0..VIEW_HEIGHT {
+        for screen_x in 0..VIEW_WIDTH {
+            let position = match camera.to_world(screen_x, screen_y) {
+                Some(position) if map.in_bounds(&position) => position,
+                _ => continue,
+            };
+            let tile = &map[&position];
+            if !tile.explored {
                 continue;
             }
-            let visible = fov_map.is_in_fov(x, y);
-            let wall = map.tiles[x as usize][y as usize].block_sight;
-            let color = match (visible, wall) {
-                // outside of field of view:
-                (false, true) => COLOR_DARK_WALL,
-                (false, false) => COLOR_DARK_GROUND,
-                // inside fov:
-                (true, true) => COLOR_LIGHT_WALL,
-                (true, false) => COLOR_LIGHT_GROUND,
-            };
-            offscreen.set_char_background(x, y, color, BackgroundFlag::Set);
+            let visible = fov_map.is_in_fov(position.x, position.y);
+            let (background, glyph) = tile_appearance(&tile.kind, visible);
+            offscreen.set_char_background(screen_x, screen_y, background, BackgroundFlag::Set);
+            if let Some((glyph, color)) = glyph {
+                offscreen.set_default_foreground(color);
+                offscreen.put_char(screen_x, screen_y, glyph, BackgroundFlag::None);
+            }
         }
     }
 }
@@ -95,6 +161,17 @@ fn draw_hp(panel: &mut Offscreen, hp: i32, max_hp: i32) {
     );
 }
 
+fn draw_burden(panel: &mut Offscreen, burden: Burden) {
+    if let Some(text) = burden.describe() {
+        panel.set_default_foreground(if burden == Burden::Strained {
+            LIGHT_RED
+        } else {
+            YELLOW
+        });
+        panel.print_ex(1, 3, BackgroundFlag::None, TextAlignment::Left, text);
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_bar(
     panel: &mut Offscreen,
@@ -156,19 +233,18 @@ fn render_names_under_mouse(panel: &mut Offscreen, names: &[String]) {
     );
 }
 
-fn render_menu(root: &mut Root, menu: &Menu) {
-    assert!(
-        menu.items.len() <= 26,
-        "Cannot have a menu with more than 26 options."
-    );
-
-    // calculate total height for the header (after auto-wrap) and one line per option
+fn render_menu(root: &mut Root, menu: &mut Menu) {
+    // calculate total height for the header (after auto-wrap) and one line per option,
+    // with a line for the page number if there's more than one page
     let header_height = if menu.header.is_empty() {
         0
     } else {
         root.get_height_rect(0, 0, menu.width, SCREEN_HEIGHT, &menu.header)
     };
-    let height = menu.items.len() as i32 + header_height;
+    let page = menu.page_range();
+    let text_height = if menu.text.is_some() { 1 } else { 0 };
+    let footer_height = if menu.num_pages() > 1 { 1 } else { 0 };
+    let height = header_height + text_height + page.len() as i32 + footer_height;
 
     // create an off-screen console that represents the menu's window
     let mut window = Offscreen::new(menu.width, height);
@@ -185,16 +261,44 @@ fn render_menu(root: &mut Root, menu: &Menu) {
         &menu.header,
     );
 
-    // print all the options
-    for (index, option_text) in menu.items.iter().enumerate() {
-        let menu_letter = (b'a' + index as u8) as char;
-        let text = format!("({}) {}", menu_letter, option_text);
+    // what's been typed so far, with a cursor after it
+    if let Some(text) = &menu.text {
+        window.print_ex(
+            0,
+            header_height,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!("> {}_", text),
+        );
+    }
+
+    // print the options on this page, greying out the ones that can't be picked
+    let items_y = header_height + text_height;
+    for (line, index) in page.clone().enumerate() {
+        let item = &menu.items[index];
+        let y = items_y + line as i32;
+        if index == menu.cursor {
+            window.set_default_background(DARKER_AMBER);
+            window.rect(0, y, menu.width, 1, false, BackgroundFlag::Set);
+        }
+        window.set_default_foreground(if item.enabled { WHITE } else { DARK_GREY });
+        let text = if menu.multi_select {
+            let tick = if item.selected { 'x' } else { ' ' };
+            format!("({}) [{}] {}", menu_letter(line), tick, item.label)
+        } else {
+            format!("({}) {}", menu_letter(line), item.label)
+        };
+        window.print_ex(0, y, BackgroundFlag::None, TextAlignment::Left, text);
+    }
+
+    if footer_height > 0 {
+        window.set_default_foreground(LIGHT_GREY);
         window.print_ex(
             0,
-            header_height + index as i32,
+            height - 1,
             BackgroundFlag::None,
             TextAlignment::Left,
-            text,
+            format!("Page {}/{}, PgUp/PgDn", menu.page() + 1, menu.num_pages()),
         );
     }
 
@@ -210,9 +314,7 @@ fn render_menu(root: &mut Root, menu: &Menu) {
         1.0,
         0.7,
     );
-
-    // present the root console to the player and wait for a key-press
-    root.flush();
+    menu.top_left = Some((x, y + items_y));
 }
 
 fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
@@ -223,6 +325,8 @@ fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
     // Get the items we'll be rendering
     let mut items = (&data.position, &data.visual, &data.entities, &data.name)
         .join()
+        // Hidden traps stay hidden until someone finds (or springs) them
+        .filter(|j| data.trap.get(j.2).map_or(true, |trap| trap.revealed))
         .filter(|j| {
             (j.1.always_visible && data.map.as_ref().unwrap()[j.0].explored)
                 || fov_map.is_in_fov(j.0.x, j.0.y)
@@ -237,6 +341,12 @@ fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
             .cmp(&data.collider.get(b.2).is_some())
     });
 
+    // Keep the player in view
+    if let Some((position, _)) = (&data.position, &data.player).join().next() {
+        data.camera.follow(position, data.map.as_ref().unwrap());
+    }
+    let camera = &*data.camera;
+
     let map = &mut consoles.map;
 
     // Clear the screen first
@@ -244,18 +354,18 @@ fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
     map.clear();
 
     // Walls and stuff
-    draw_fov(map, data.map.as_ref().unwrap(), fov_map);
+    draw_fov(map, camera, data.map.as_ref().unwrap(), fov_map);
 
     // Monsters and stuff
     for (position, visual, _, _) in &items {
-        draw_object(map, position, visual);
+        draw_object(map, camera, position, visual);
     }
 
     // Blit the map
     blit(
         &*map,
         (0, 0),
-        (MAP_WIDTH, MAP_HEIGHT),
+        (VIEW_WIDTH, VIEW_HEIGHT),
         &mut consoles.root,
         (0, 0),
         1.0,
@@ -267,19 +377,19 @@ fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
     if let Some((living, _)) = (&data.living, &data.player).join().next() {
         draw_hp(panel, living.hp, living.max_hp);
     }
+    if let Some((burden, _)) = (&data.burden, &data.player).join().next() {
+        draw_burden(panel, *burden);
+    }
     render_messages(panel, &(*data.messages).inner);
 
     // Mouse look
-    let mouse_pos = Position {
-        x: data.mouse.cx as i32,
-        y: data.mouse.cy as i32,
-    };
+    let mouse_pos = camera.to_world(data.mouse.cx as i32, data.mouse.cy as i32);
     render_names_under_mouse(
         panel,
         &items
             .iter()
-            .filter(|j| j.0 == &mouse_pos)
-            .map(|j| (j.3).0.clone())
+            .filter(|j| Some(j.0) == mouse_pos.as_ref())
+            .map(|j| data.identification.name_of(data.item.get(j.2), j.3))
             .collect::<Vec<_>>(),
     );
 
@@ -296,10 +406,13 @@ fn view_game(consoles: &mut UIConsoles, data: &mut RenderSystemData) {
 }
 
 fn view_main_menu(consoles: &mut UIConsoles) {
-    let img = tcod::image::Image::from_file("assets/menu_background.png")
-        .ok()
-        .expect("Background image not found");
-    tcod::image::blit_2x(&img, (0, 0), (-1, -1), &mut consoles.root, (0, 0));
+    // REXPaint art takes precedence over the plain image, if there is any
+    if !rexpaint::draw_file(&mut consoles.root, "assets/menu_background.xp", 0, 0) {
+        let img = tcod::image::Image::from_file("assets/menu_background.png")
+            .ok()
+            .expect("Background image not found");
+        tcod::image::blit_2x(&img, (0, 0), (-1, -1), &mut consoles.root, (0, 0));
+    }
     consoles.root.set_default_foreground(LIGHT_YELLOW);
     consoles.root.print_ex(
         SCREEN_WIDTH / 2,
@@ -333,11 +446,11 @@ impl<'a> System<'a> for RenderSystem {
 
         prepare_for_new_frame(consoles, &mut data);
         match *data.state {
-            State::Game => view_game(consoles, &mut data),
+            State::Game | State::Descending | State::GameOver => view_game(consoles, &mut data),
             State::MainMenu => view_main_menu(consoles),
             State::Loaded => (),
         };
-        if let Some(m) = data.menu.as_ref() {
+        if let Some(m) = data.menu.as_mut() {
             render_menu(&mut consoles.root, m);
         };
 
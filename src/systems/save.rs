@@ -1,17 +1,19 @@
-use std::fs::File;
+use std::fs;
 
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use shred_derive::SystemData;
 use specs::{
-    error::NoError,
     prelude::*,
-    saveload::{MarkerAllocator, SerializeComponents, U64Marker, U64MarkerAllocator},
+    saveload::{MarkerAllocator, U64Marker, U64MarkerAllocator},
     Write,
 };
 use specs_derive::Component;
 
-use crate::resources::messages::Messages;
-use crate::{components::*, resources::map::Map};
+use crate::resources::{
+    identification::Identification, map::Map, messages::Messages, save_error::SaveError,
+    save_slot::SaveSlot,
+};
+use crate::save_format::{SaveBackend, SaveHeader, SavedStorages};
 
 #[derive(PartialEq, Serialize, Deserialize, Component, Debug, Clone)]
 #[storage(HashMapStorage)]
@@ -27,6 +29,9 @@ pub struct SavePrepSystemData<'a> {
     messages_res: Read<'a, Messages>,
     messages_comp: WriteStorage<'a, Messages>,
 
+    identification_res: Read<'a, Identification>,
+    identification_comp: WriteStorage<'a, Identification>,
+
     synthetic_marker: WriteStorage<'a, Synthetic>,
     allocator: Write<'a, U64MarkerAllocator>,
     marker: WriteStorage<'a, U64Marker>,
@@ -54,45 +59,55 @@ impl<'a> System<'a> for SavePrepSystem {
         data.messages_comp
             .insert(resources_entity, data.messages_res.clone())
             .unwrap();
+        data.identification_comp
+            .insert(resources_entity, data.identification_res.clone())
+            .unwrap();
     }
 }
 
 #[derive(SystemData)]
 pub struct SaveSystemData<'a> {
     entity: Entities<'a>,
-    components0: (
-        ReadStorage<'a, Collider>,
-        ReadStorage<'a, Inventory>,
-        ReadStorage<'a, Item>,
-        ReadStorage<'a, Living>,
-        ReadStorage<'a, Map>,
-        ReadStorage<'a, Messages>,
-        ReadStorage<'a, Name>,
-        ReadStorage<'a, Player>,
-        ReadStorage<'a, Position>,
-        ReadStorage<'a, PreviousPosition>,
-        ReadStorage<'a, Power>,
-        ReadStorage<'a, Synthetic>,
-        ReadStorage<'a, Velocity>,
-        ReadStorage<'a, Visual>,
-        ReadStorage<'a, Energy>,
-        WriteStorage<'a, Action>,
-    ),
-    components1: (ReadStorage<'a, Ai>,),
+    storages: SavedStorages<'a>,
     synthetic_marker: ReadStorage<'a, Synthetic>,
     marker: ReadStorage<'a, U64Marker>,
+
+    map: ReadExpect<'a, Map>,
+    slot: Read<'a, SaveSlot>,
+    backend: Read<'a, SaveBackend>,
+    error: Write<'a, Option<SaveError>>,
 }
 
-macro_rules! do_ser {
-    ($ser:expr, $data:ident.$field:ident) => {
-        SerializeComponents::<NoError, U64Marker>::serialize(
-            &$data.$field,
-            &$data.entity,
-            &$data.marker,
-            &mut $ser,
-        )
-        .unwrap();
-    };
+/// All saved components, keyed by name so the order they're written in doesn't matter
+struct SavedComponents<'s, 'a>(&'s SaveSystemData<'a>);
+
+impl<'s, 'a> Serialize for SavedComponents<'s, 'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // bincode wants to know the length up front
+        let mut map = serializer.serialize_map(Some(SavedStorages::COUNT))?;
+        self.0
+            .storages
+            .write(&mut map, &self.0.marker, *self.0.backend)?;
+        map.end()
+    }
+}
+
+fn save(data: &SaveSystemData) -> Result<(), SaveError> {
+    // Serialize, header first so loading knows what it's dealing with
+    let (name, turns) = (&data.storages.name, &data.storages.player)
+        .join()
+        .next()
+        .map_or(("", 0), |(name, player)| (name.0.as_str(), player.turns));
+    let header = SaveHeader::current(name, data.map.depth, turns);
+    let bytes = data.backend.encode(&header, &SavedComponents(data))?;
+
+    // Write to disk
+    let path = data.slot.path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, bytes)?;
+    Ok(())
 }
 
 pub struct SaveSystem;
@@ -100,21 +115,13 @@ pub struct SaveSystem;
 impl<'a> System<'a> for SaveSystem {
     type SystemData = SaveSystemData<'a>;
 
-    fn run(&mut self, data: Self::SystemData) {
-        use std::io::Write;
-
-        // Serialize
-        let mut ser = ron::ser::Serializer::new(None, false);
-        do_ser!(ser, data.components0);
-        do_ser!(ser, data.components1);
-
-        // Write to disk
-        let mut file = File::create("savegame").unwrap();
-        file.write_all(ser.into_output_string().as_bytes()).unwrap();
+    fn run(&mut self, mut data: Self::SystemData) {
+        let result = save(&data);
 
-        // Clean any entities created by SavePrepSystem
+        // Clean any entities created by SavePrepSystem, whether saving worked or not
         for (entity, _) in (&data.entity, &data.synthetic_marker).join() {
             data.entity.delete(entity).unwrap();
         }
+        *data.error = result.err();
     }
 }
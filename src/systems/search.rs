@@ -0,0 +1,102 @@
+use rand::Rng;
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{
+    components::*,
+    resources::{messages::Messages, state::State},
+};
+
+const SEARCH_RADIUS: f32 = 2.5;
+const SEARCH_CHANCE: f64 = 0.5;
+const DISARM_CHANCE: f64 = 0.7;
+
+pub struct SearchSystem;
+
+#[derive(SystemData)]
+pub struct SearchSystemData<'a> {
+    action: ReadStorage<'a, Action>,
+    energy: WriteStorage<'a, Energy>,
+    name: ReadStorage<'a, Name>,
+    player: ReadStorage<'a, Player>,
+    position: ReadStorage<'a, Position>,
+    trap: WriteStorage<'a, Trap>,
+
+    entity: Entities<'a>,
+    messages: Write<'a, Messages>,
+    state: ReadExpect<'a, State>,
+}
+
+impl<'a> System<'a> for SearchSystem {
+    type SystemData = SearchSystemData<'a>;
+
+    /// Handles both looking for hidden traps and disarming the ones that have been found
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        for (action, energy, position, _) in
+            (&data.action, &mut data.energy, &data.position, &data.player).join()
+        {
+            match action {
+                Action::Search => {
+                    if !energy.consume(action.energy_cost()) {
+                        continue;
+                    }
+                    let mut found = 0;
+                    for (trap, trap_position) in (&mut data.trap, &data.position).join() {
+                        if trap.revealed || trap_position.distance_to(position) > SEARCH_RADIUS {
+                            continue;
+                        }
+                        if rand::thread_rng().gen_bool(SEARCH_CHANCE) {
+                            trap.revealed = true;
+                            found += 1;
+                        }
+                    }
+                    if found > 0 {
+                        data.messages
+                            .push("You find a hidden trap!", colors::LIGHT_ORANGE);
+                    } else {
+                        data.messages
+                            .push("You search, but find nothing.", colors::WHITE);
+                    }
+                }
+
+                Action::Disarm => {
+                    let target = (&data.entity, &data.trap, &data.position)
+                        .join()
+                        .find(|j| j.1.revealed && j.2.distance_to(position) < 1.5)
+                        .map(|j| j.0);
+                    match target {
+                        Some(trap) => {
+                            if !energy.consume(action.energy_cost()) {
+                                continue;
+                            }
+                            let trap_name = &data.name.get(trap).unwrap().0;
+                            if rand::thread_rng().gen_bool(DISARM_CHANCE) {
+                                data.messages.push(
+                                    format!("You disarm the {}.", trap_name),
+                                    colors::LIGHT_GREEN,
+                                );
+                                data.entity.delete(trap).unwrap();
+                            } else {
+                                data.messages.push(
+                                    format!("You fail to disarm the {}.", trap_name),
+                                    colors::ORANGE,
+                                );
+                            }
+                        }
+                        None => {
+                            data.messages
+                                .push("There's no known trap next to you.", colors::WHITE);
+                        }
+                    }
+                }
+
+                _ => (),
+            }
+        }
+    }
+}
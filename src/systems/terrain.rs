@@ -0,0 +1,95 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{
+    components::*,
+    resources::{
+        map::{Map, TileKind},
+        messages::Messages,
+        state::State,
+    },
+};
+
+const LAVA_DAMAGE: i32 = 10;
+const DROWNING_DAMAGE: i32 = 2;
+const FALL_DAMAGE: i32 = 3;
+
+pub struct TerrainSystem;
+
+#[derive(SystemData)]
+pub struct TerrainSystemData<'a> {
+    living: WriteStorage<'a, Living>,
+    name: ReadStorage<'a, Name>,
+    player: ReadStorage<'a, Player>,
+    position: ReadStorage<'a, Position>,
+    velocity: WriteStorage<'a, Velocity>,
+    burden: ReadStorage<'a, Burden>,
+
+    map: Option<ReadExpect<'a, Map>>,
+    messages: Write<'a, Messages>,
+    state: Write<'a, State>,
+}
+
+impl<'a> System<'a> for TerrainSystem {
+    type SystemData = TerrainSystemData<'a>;
+
+    /// Runs after collision detection, but before the movement actually happens, so that we
+    /// know exactly which tile is being entered.
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        let map = data.map.as_ref().unwrap();
+        for (position, velocity, living, name, player, burden) in (
+            &data.position,
+            &mut data.velocity,
+            &mut data.living,
+            &data.name,
+            (&data.player).maybe(),
+            (&data.burden).maybe(),
+        )
+            .join()
+        {
+            if velocity.magnitude == 0 || !living.alive {
+                continue;
+            }
+            let entering = position + &*velocity;
+            match map[&entering].kind {
+                TileKind::Lava => {
+                    data.messages.push(
+                        format!("The lava burns {} for {} hit points!", name.0, LAVA_DAMAGE),
+                        colors::ORANGE,
+                    );
+                    living.take_damage(LAVA_DAMAGE, "lava", false);
+                }
+                TileKind::DeepWater => {
+                    // Heavy packs drag you under
+                    let damage =
+                        DROWNING_DAMAGE * burden.map_or(1, |b| b.energy_multiplier()) as i32;
+                    data.messages.push(
+                        format!(
+                            "{} struggles to stay afloat, and loses {} hit points.",
+                            name.0, damage
+                        ),
+                        colors::LIGHT_BLUE,
+                    );
+                    living.take_damage(damage, "drowning", false);
+                }
+                // Monsters won't step onto hazards, so only the player can fall in
+                TileKind::Chasm if player.is_some() => {
+                    data.messages.push(
+                        "You fall into the chasm, and land hard on the level below!",
+                        colors::RED,
+                    );
+                    living.take_damage(FALL_DAMAGE, "a fall", false);
+                    // We'll be on a new map, don't move anywhere on it
+                    velocity.magnitude = 0;
+                    *data.state = State::Descending;
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{
+    components::*,
+    resources::{identification::Identification, map::Map, messages::Messages, state::State},
+    systems::{
+        projectile::fly,
+        use_item::{heal, HEAL_AMOUNT},
+    },
+};
+
+const SPLASH_RADIUS: f32 = 1.5;
+/// What anything without its own `ThrownDamage` does when it hits
+const DEFAULT_THROWN_DAMAGE: i32 = 1;
+
+pub struct ThrowSystem;
+
+#[derive(SystemData)]
+pub struct ThrowSystemData<'a> {
+    action: ReadStorage<'a, Action>,
+    collider: ReadStorage<'a, Collider>,
+    energy: WriteStorage<'a, Energy>,
+    inventory: WriteStorage<'a, Inventory>,
+    item: ReadStorage<'a, Item>,
+    living: WriteStorage<'a, Living>,
+    name: ReadStorage<'a, Name>,
+    position: WriteStorage<'a, Position>,
+    thrown_damage: ReadStorage<'a, ThrownDamage>,
+
+    entity: Entities<'a>,
+    identification: Write<'a, Identification>,
+    map: Option<ReadExpect<'a, Map>>,
+    messages: Write<'a, Messages>,
+    state: ReadExpect<'a, State>,
+}
+
+impl<'a> System<'a> for ThrowSystem {
+    type SystemData = ThrowSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        let throws: Vec<(Entity, usize, Position)> = (&data.entity, &data.action)
+            .join()
+            .filter_map(|(entity, action)| match action {
+                Action::Throw {
+                    inventory_index,
+                    target,
+                } => Some((entity, *inventory_index, target.clone())),
+                _ => None,
+            })
+            .collect();
+
+        for (thrower, inventory_index, target) in throws {
+            throw(thrower, inventory_index, &target, &mut data);
+        }
+    }
+}
+
+fn throw(thrower: Entity, inventory_index: usize, target: &Position, data: &mut ThrowSystemData) {
+    let origin = match data.position.get(thrower) {
+        Some(position) => position.clone(),
+        None => return,
+    };
+    if data
+        .inventory
+        .get(thrower)
+        .and_then(|inventory| inventory.get(inventory_index))
+        .is_none()
+    {
+        return;
+    }
+
+    let action = data.action.get(thrower).unwrap().clone();
+    if !data
+        .energy
+        .get_mut(thrower)
+        .map_or(false, |energy| energy.consume(action.energy_cost()))
+    {
+        return;
+    }
+
+    // Only one item of a stack is thrown at a time
+    let missile = data
+        .inventory
+        .get_mut(thrower)
+        .unwrap()
+        .take(inventory_index)
+        .unwrap();
+    let missile_name = data
+        .identification
+        .name_of(data.item.get(missile), data.name.get(missile).unwrap());
+    data.messages
+        .push(format!("You throw the {}.", missile_name), colors::WHITE);
+
+    let blockers: Vec<(Entity, Position)> =
+        (&data.entity, &data.position, &data.collider, &data.living)
+            .join()
+            .filter(|j| j.3.alive)
+            .map(|j| (j.0, j.1.clone()))
+            .collect();
+    let flight = fly(data.map.as_ref().unwrap(), &origin, target, &blockers);
+
+    // Potions break on impact, everything else can be picked up again
+    if data.item.get(missile) == Some(&Item::Heal) {
+        shatter(missile, &missile_name, &flight.landing, data);
+        return;
+    }
+
+    if let Some(victim) = flight.hit {
+        let damage = data
+            .thrown_damage
+            .get(missile)
+            .map_or(DEFAULT_THROWN_DAMAGE, |d| d.0);
+        let living = data.living.get_mut(victim).unwrap();
+        let victim_name = &data.name.get(victim).unwrap().0;
+        let damage = damage - living.defense;
+        if damage > 0 {
+            data.messages.push(
+                format!(
+                    "The {} hits {} for {} hit points.",
+                    missile_name, victim_name, damage
+                ),
+                colors::WHITE,
+            );
+            living.take_damage(damage, format!("the {}", missile_name), true);
+        } else {
+            data.messages.push(
+                format!("The {} bounces off {}.", missile_name, victim_name),
+                colors::WHITE,
+            );
+        }
+    }
+
+    data.position.insert(missile, flight.landing).unwrap();
+}
+
+fn shatter(potion: Entity, potion_name: &str, landing: &Position, data: &mut ThrowSystemData) {
+    data.messages.push(
+        format!("The {} shatters!", potion_name),
+        colors::LIGHT_VIOLET,
+    );
+
+    let mut splashed = false;
+    for (position, living, name) in (&data.position, &mut data.living, &data.name).join() {
+        if living.alive && position.distance_to(landing) <= SPLASH_RADIUS {
+            heal(living, HEAL_AMOUNT);
+            data.messages.push(
+                format!("The {} looks healthier.", name.0),
+                colors::LIGHT_VIOLET,
+            );
+            splashed = true;
+        }
+    }
+
+    // Seeing the effect is enough to tell what the potion was
+    if splashed && data.identification.identify(&Item::Heal) {
+        data.messages.push(
+            format!("That was a {}.", data.name.get(potion).unwrap().0),
+            colors::WHITE,
+        );
+    }
+
+    data.entity.delete(potion).unwrap();
+}
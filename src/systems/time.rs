@@ -8,7 +8,7 @@ pub struct TimeSystem;
 #[derive(SystemData)]
 pub struct TimeSystemData<'a> {
     energy: WriteStorage<'a, Energy>,
-    player: ReadStorage<'a, Player>,
+    player: WriteStorage<'a, Player>,
     living: ReadStorage<'a, Living>,
 }
 
@@ -40,5 +40,8 @@ impl<'a> System<'a> for TimeSystem {
         for energy in (&mut data.energy).join() {
             energy.gain(1);
         }
+        for player in (&mut data.player).join() {
+            player.turns += 1;
+        }
     }
 }
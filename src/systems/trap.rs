@@ -0,0 +1,188 @@
+use rand::Rng;
+use shred_derive::SystemData;
+use specs::prelude::*;
+use tcod::colors;
+
+use crate::{
+    components::*,
+    resources::{map::Map, messages::Messages, state::State},
+};
+
+const SPIKE_DAMAGE: i32 = 5;
+const ALARM_RADIUS: f32 = 20.0;
+const ALARM_NUM_TURNS: i32 = 20;
+const GAS_NUM_TURNS: i32 = 5;
+
+pub struct TrapSystem;
+
+#[derive(SystemData)]
+pub struct TrapSystemData<'a> {
+    ai: WriteStorage<'a, Ai>,
+    collider: ReadStorage<'a, Collider>,
+    confused: WriteStorage<'a, Confused>,
+    living: WriteStorage<'a, Living>,
+    name: ReadStorage<'a, Name>,
+    player: ReadStorage<'a, Player>,
+    position: WriteStorage<'a, Position>,
+    trap: WriteStorage<'a, Trap>,
+    velocity: WriteStorage<'a, Velocity>,
+
+    entity: Entities<'a>,
+    map: Option<ReadExpect<'a, Map>>,
+    messages: Write<'a, Messages>,
+    state: Write<'a, State>,
+}
+
+impl<'a> System<'a> for TrapSystem {
+    type SystemData = TrapSystemData<'a>;
+
+    /// Like `TerrainSystem`, runs between collision detection and movement, and looks at the
+    /// tile each moving entity is about to enter.
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.state != State::Game {
+            return;
+        }
+
+        let triggered: Vec<(Entity, Entity)> =
+            (&data.entity, &data.position, &data.velocity, &data.living)
+                .join()
+                .filter(|j| j.2.magnitude > 0 && j.3.alive)
+                .filter_map(|(victim, position, velocity, _)| {
+                    let entering = position + velocity;
+                    (&data.entity, &data.position, &data.trap)
+                        .join()
+                        .find(|j| j.1 == &entering)
+                        .map(|j| (victim, j.0))
+                })
+                .collect();
+
+        for (victim, trap) in triggered {
+            spring(victim, trap, &mut data);
+        }
+    }
+}
+
+fn spring(victim: Entity, trap: Entity, data: &mut TrapSystemData) {
+    let is_player = data.player.get(victim).is_some();
+    let victim_name = data.name.get(victim).unwrap().0.clone();
+    let trap_name = data.name.get(trap).unwrap().0.clone();
+
+    // Whatever happens next, everyone now knows there's a trap here
+    let kind = {
+        let trap = data.trap.get_mut(trap).unwrap();
+        trap.revealed = true;
+        trap.kind
+    };
+    data.messages.push(
+        format!("{} triggers a {}!", victim_name, trap_name),
+        colors::ORANGE,
+    );
+
+    match kind {
+        TrapKind::Spikes => {
+            data.messages.push(
+                format!(
+                    "{} is impaled for {} hit points.",
+                    victim_name, SPIKE_DAMAGE
+                ),
+                colors::ORANGE,
+            );
+            data.living
+                .get_mut(victim)
+                .unwrap()
+                .take_damage(SPIKE_DAMAGE, "a spike trap", false);
+        }
+
+        TrapKind::Teleport => {
+            let destination = random_free_position(data);
+            data.velocity.get_mut(victim).unwrap().magnitude = 0;
+            data.position.insert(victim, destination).unwrap();
+            data.messages.push(
+                format!("{} vanishes in a flash of light!", victim_name),
+                colors::LIGHT_VIOLET,
+            );
+        }
+
+        TrapKind::Alarm => {
+            data.messages
+                .push("A deafening alarm rings through the dungeon!", colors::RED);
+            let origin = data.position.get(trap).unwrap().clone();
+            for (ai, position, _) in (&mut data.ai, &data.position, !&data.player).join() {
+                if position.distance_to(&origin) > ALARM_RADIUS {
+                    continue;
+                }
+                if let Ai::Alerted { num_turns, .. } = ai {
+                    *num_turns = ALARM_NUM_TURNS;
+                    continue;
+                }
+                *ai = Ai::Alerted {
+                    previous_ai: Box::new(ai.clone()),
+                    num_turns: ALARM_NUM_TURNS,
+                };
+            }
+        }
+
+        TrapKind::ConfusionGas => {
+            data.messages.push(
+                format!("A cloud of gas engulfs {}!", victim_name),
+                colors::LIGHT_GREEN,
+            );
+            // The player's moves come from the keyboard, so confusing their AI would hand the
+            // controls over to it. Their moves get scrambled instead.
+            if is_player {
+                data.messages
+                    .push("You feel dizzy and confused.", colors::LIGHT_GREEN);
+                data.confused
+                    .insert(victim, Confused(GAS_NUM_TURNS))
+                    .unwrap();
+            } else if let Some(ai) = data.ai.get_mut(victim) {
+                *ai = match ai {
+                    Ai::Confused { previous_ai, .. } => Ai::Confused {
+                        previous_ai: previous_ai.clone(),
+                        num_turns: GAS_NUM_TURNS,
+                    },
+                    other => Ai::Confused {
+                        previous_ai: Box::new(other.clone()),
+                        num_turns: GAS_NUM_TURNS,
+                    },
+                };
+            }
+        }
+
+        TrapKind::Trapdoor => {
+            data.velocity.get_mut(victim).unwrap().magnitude = 0;
+            if is_player {
+                data.messages
+                    .push("The floor gives way under your feet!", colors::RED);
+                *data.state = State::Descending;
+            } else {
+                data.messages.push(
+                    format!("{} falls through the trapdoor!", victim_name),
+                    colors::ORANGE,
+                );
+                data.entity.delete(victim).unwrap();
+            }
+        }
+    }
+}
+
+fn random_free_position(data: &TrapSystemData) -> Position {
+    let map = data.map.as_ref().unwrap();
+    loop {
+        let position = Position {
+            x: rand::thread_rng().gen_range(0, map.width),
+            y: rand::thread_rng().gen_range(0, map.height),
+        };
+        let tile = &map[&position];
+        if tile.blocked() || tile.kind.is_hazardous() {
+            continue;
+        }
+        if (&data.position, &data.collider)
+            .join()
+            .any(|j| j.0 == &position)
+        {
+            continue;
+        }
+        return position;
+    }
+}
@@ -7,9 +7,11 @@ use tcod::map::Map as FovMap;
 
 use crate::components::*;
 use crate::resources::{
+    identification::Identification,
     input_action::InputAction,
+    keymap::Keymap,
     messages::Messages,
-    targeting::{Targeting, TargetingKind},
+    targeting::{Targeting, TargetingIntent, TargetingKind},
 };
 use tcod::colors;
 
@@ -28,6 +30,8 @@ pub struct UseItemSystemData<'a> {
     ai: WriteStorage<'a, Ai>,
 
     messages: Write<'a, Messages>,
+    keymap: Read<'a, Keymap>,
+    identification: Write<'a, Identification>,
     targeting: WriteExpect<'a, Option<Targeting>>,
     fov_map: Option<ReadExpect<'a, Arc<Mutex<FovMap>>>>,
 
@@ -42,10 +46,10 @@ impl<'a> System<'a> for UseItemSystem {
         {
             match *action {
                 Action::UseFromInventory { inventory_index } => {
-                    if inventory_index >= inventory.0.len() {
-                        continue;
-                    }
-                    let item_entity = *inventory.0.get(inventory_index).unwrap();
+                    let item_entity = match inventory.get(inventory_index) {
+                        Some(item_entity) => item_entity,
+                        None => continue,
+                    };
                     let opt_item = data.item.get(item_entity);
                     if let Some(item) = opt_item {
                         let result = match item {
@@ -59,12 +63,36 @@ impl<'a> System<'a> for UseItemSystem {
                             Item::Lightning => UseResult::Cancelled, //cast_lightning,
                             Item::Confuse => UseResult::Cancelled,   //target_confuse,
                             Item::Fireball => UseResult::Cancelled,  //target_fireball,
+                            Item::Key => {
+                                data.messages
+                                    .push("Walk into a locked door to unlock it.", colors::WHITE);
+                                UseResult::Cancelled
+                            }
+                            Item::Weapon | Item::Ammo => {
+                                let hint = match data.keymap.keys_for(&InputAction::Fire).first() {
+                                    Some(key) => format!("Press {} to fire a missile weapon.", key),
+                                    None => "Firing a missile weapon has no key, choose one \
+                                             on the key bindings screen."
+                                        .to_string(),
+                                };
+                                data.messages.push(hint, colors::WHITE);
+                                UseResult::Cancelled
+                            }
                         };
 
                         match result {
                             UseResult::UsedUp => {
+                                if data.identification.identify(item) {
+                                    data.messages.push(
+                                        format!(
+                                            "That was a {}.",
+                                            data.name.get(item_entity).unwrap().0
+                                        ),
+                                        colors::WHITE,
+                                    );
+                                }
                                 // destroy after use, unless it was cancelled for some reason
-                                inventory.0.retain(|&x| x != item_entity);
+                                inventory.remove(item_entity);
                                 data.entities.delete(item_entity).unwrap();
                             }
                             UseResult::Cancelled => {
@@ -77,7 +105,7 @@ impl<'a> System<'a> for UseItemSystem {
                         data.messages.push(
                             format!(
                                 "The {} cannot be used.",
-                                data.name.get(inventory.0[inventory_index]).unwrap().0
+                                data.name.get(item_entity).unwrap().0
                             ),
                             colors::WHITE,
                         );
@@ -96,7 +124,7 @@ enum UseResult {
     Targeting,
 }
 
-const HEAL_AMOUNT: i32 = 4;
+pub const HEAL_AMOUNT: i32 = 4;
 
 fn cast_heal(
     action: &Action,
@@ -146,7 +174,10 @@ fn cast_lightning(_entity: Entity, data: &mut UseItemSystemData, action: Action)
                 ),
                 colors::LIGHT_BLUE,
             );
-            data.living.get_mut(monster).unwrap().hp -= LIGHTNING_DAMAGE;
+            data.living
+                .get_mut(monster)
+                .unwrap()
+                .take_damage(LIGHTNING_DAMAGE, "lightning", true);
             UseResult::UsedUp
         } else {
             UseResult::NotEnoughEnergy
@@ -166,6 +197,7 @@ fn target_confuse(entity: Entity, data: &mut UseItemSystemData, action: Action)
     *data.targeting = Some(Targeting {
         used_item: entity,
         kind: TargetingKind::Monster,
+        intent: TargetingIntent::UseItem,
         max_range: Some(CONFUSE_RANGE),
     });
     data.messages.push(
@@ -208,6 +240,7 @@ fn target_fireball(entity: Entity, data: &mut UseItemSystemData, action: Action)
     *data.targeting = Some(Targeting {
         used_item: entity,
         kind: TargetingKind::Tile,
+        intent: TargetingIntent::UseItem,
         max_range: None,
     });
     data.messages.push(
@@ -234,7 +267,7 @@ fn cast_fireball(position: &Position, data: &mut UseItemSystemData) -> UseResult
                 ),
                 colors::ORANGE,
             );
-            living.hp -= FIREBALL_DAMAGE;
+            living.take_damage(FIREBALL_DAMAGE, "a fireball", true);
         }
     }
     UseResult::UsedUp